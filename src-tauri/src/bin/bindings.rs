@@ -0,0 +1,34 @@
+//! 生成页面使用的 TypeScript 绑定.
+//! `cargo run --features bindings --bin bindings` 写入文件, 加上 `-- --check` 时只检查文件是否过期.
+//! 两种情况都会先检查 `commands()` 与 connect.rs 中的命令函数是否一致
+
+use std::{fs, path::PathBuf, process};
+
+fn main() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../src/utils/client/bindings.ts");
+    // 命令表与命令函数不一致时生成的类型是错的, 直接失败
+    if let Err(errors) = app::bindings::check_commands() {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        process::exit(1);
+    }
+    let content = app::bindings::typescript();
+
+    if std::env::args().any(|arg| arg == "--check") {
+        if fs::read_to_string(&path).unwrap_or_default() != content {
+            eprintln!(
+                "{} is out of date, run `npm run bindings` to regenerate",
+                path.display()
+            );
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(error) = fs::write(&path, content) {
+        eprintln!("write {} error: {}", path.display(), error);
+        process::exit(1);
+    }
+    println!("write {}", path.display());
+}
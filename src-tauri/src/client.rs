@@ -1,5 +0,0 @@
-pub mod c_macro;
-pub mod error;
-pub mod w_client;
-pub mod utils;
-pub mod client_manage;
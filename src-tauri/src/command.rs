@@ -1,5 +1,10 @@
+use std::sync::Arc;
+
+use tauri::State;
 use uuid::Uuid;
 
+use crate::logger::{LogLevels, LogLevelsSnapshot};
+
 #[tauri::command]
 pub fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
@@ -11,3 +16,23 @@ pub fn generate_unique_message_id() -> String {
     uuid.to_string()
 }
 
+/// 修改日志级别, `module` 为模块路径 (如 `client::w_client`), 为空时修改默认级别;
+/// `level` 为空时移除该模块的单独设置
+#[tauri::command]
+pub fn set_log_level(
+    module: Option<String>,
+    level: Option<String>,
+    levels: State<'_, Arc<LogLevels>>,
+) -> Result<LogLevelsSnapshot, String> {
+    match (module, level) {
+        (module, Some(level)) => levels.set(module.as_deref(), &level)?,
+        (Some(module), None) => levels.reset(&module),
+        (None, None) => return Err("level is required".to_string()),
+    }
+    Ok(levels.snapshot())
+}
+
+#[tauri::command]
+pub fn log_levels(levels: State<'_, Arc<LogLevels>>) -> LogLevelsSnapshot {
+    levels.snapshot()
+}
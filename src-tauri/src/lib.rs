@@ -1,27 +1,18 @@
 pub mod command;
-mod client;
+pub mod logger;
 mod plugin;
-use log::LevelFilter;
-use tauri::Wry;
 
-use command::generate_unique_message_id;
-use tauri_plugin_log::{LogTarget};
+#[cfg(feature = "bindings")]
+pub use plugin::bindings;
+pub use plugin::handle::ConnectHandle;
+use std::sync::Arc;
 
-enum LogLevel {
-    // 定义你的日志级别
-    Info,
-    // 其他日志级别
-}
+use log::LevelFilter;
+use tauri::{Manager, Wry};
 
-// 将自定义的 LogLevel 转换为 log::LevelFilter
-impl Into<LevelFilter> for LogLevel {
-    fn into(self) -> LevelFilter {
-        match self {
-            LogLevel::Info => LevelFilter::Info,
-            // 其他日志级别的映射
-        }
-    }
-}
+use command::{generate_unique_message_id, log_levels, set_log_level};
+use logger::{prune_logs, LogLevels, LOG_KEEP_FILES, LOG_MAX_FILE_SIZE};
+use tauri_plugin_log::{LogTarget, RotationStrategy};
 
 pub struct NApp {
     builder: tauri::Builder<Wry>,
@@ -29,18 +20,37 @@ pub struct NApp {
 
 impl NApp {
     pub fn new() -> NApp {
-        // 在你的代码中使用 LogLevel::Info 并进行转换
-        let log_level = LogLevel::Info;
-        let level_filter: LevelFilter = log_level.into();
+        // 插件本身放行所有级别, 实际的过滤交给可以运行时修改的 LogLevels
+        let log_levels = Arc::new(LogLevels::new(LevelFilter::Info));
+        let filter_levels = log_levels.clone();
         let builder = tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![generate_unique_message_id])
-        .plugin(tauri_plugin_log::Builder::default().targets([
-            LogTarget::LogDir,
-            LogTarget::Stdout,
-            LogTarget::Webview,
-        ]).level(level_filter).build())
-        .plugin(tauri_plugin_store::Builder::default().build())
-        .plugin(plugin::connect::Builder::default().build());
+            .invoke_handler(tauri::generate_handler![
+                generate_unique_message_id,
+                set_log_level,
+                log_levels
+            ])
+            .manage(log_levels)
+            .plugin(
+                tauri_plugin_log::Builder::default()
+                    .targets([LogTarget::LogDir, LogTarget::Stdout, LogTarget::Webview])
+                    .level(LevelFilter::Trace)
+                    .filter(move |metadata| filter_levels.enabled(metadata))
+                    .max_file_size(LOG_MAX_FILE_SIZE)
+                    .rotation_strategy(RotationStrategy::KeepAll)
+                    .build(),
+            )
+            .setup(|app| {
+                // 日志插件只在启动时滚动, 滚动之后清理多余的旧文件
+                if let Some(dir) = app.path_resolver().app_log_dir() {
+                    let current = format!("{}.log", app.package_info().name);
+                    if let Err(err) = prune_logs(&dir, &current, LOG_KEEP_FILES) {
+                        log::warn!("prune logs error: {}", err);
+                    }
+                }
+                Ok(())
+            })
+            .plugin(tauri_plugin_store::Builder::default().build())
+            .plugin(plugin::connect::Builder::default().build());
         NApp { builder }
     }
 
@@ -66,4 +76,4 @@ impl NApp {
 
 pub fn lmian() {
     NApp::new().run()
-}
\ No newline at end of file
+}
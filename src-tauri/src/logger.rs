@@ -0,0 +1,110 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::RwLock,
+};
+
+use log::{LevelFilter, Metadata};
+use serde::Serialize;
+
+/// 启动时日志文件超过该大小会滚动, 旧文件改名保留 (`RotationStrategy::KeepAll`)
+pub const LOG_MAX_FILE_SIZE: u128 = 10 * 1024 * 1024;
+
+/// 滚动后保留的旧日志文件数, 更早的由 `prune_logs` 删除
+pub const LOG_KEEP_FILES: usize = 3;
+
+/// 删除日志目录中多余的旧日志, 按修改时间保留最新的 `keep` 个, 返回删除的文件.
+/// `current` 为正在写入的文件名, 不会被删除
+pub fn prune_logs(dir: &Path, current: &str, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let mut logs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_log = path
+            .extension()
+            .map_or(false, |extension| extension == "log");
+        if !is_log || entry.file_name() == current || !entry.file_type()?.is_file() {
+            continue;
+        }
+        logs.push((entry.metadata()?.modified()?, path));
+    }
+    logs.sort();
+    let remove = logs.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for (_, path) in logs.into_iter().take(remove) {
+        fs::remove_file(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLevelsSnapshot {
+    pub default: String,
+    pub modules: HashMap<String, String>,
+}
+
+/// 运行时可调整的日志级别, 按模块路径前缀匹配, 最长的前缀优先
+pub struct LogLevels {
+    default: RwLock<LevelFilter>,
+    modules: RwLock<HashMap<String, LevelFilter>>,
+}
+
+impl LogLevels {
+    pub fn new(default: LevelFilter) -> Self {
+        Self {
+            default: RwLock::new(default),
+            modules: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let modules = self.modules.read().unwrap();
+        modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module.as_str() || target.starts_with(&format!("{}::", module))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(*self.default.read().unwrap())
+    }
+
+    pub fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    /// `module` 为空时修改默认级别, `level` 为 `off|error|warn|info|debug|trace`
+    pub fn set(&self, module: Option<&str>, level: &str) -> Result<(), String> {
+        let level = LevelFilter::from_str(level).map_err(|err| err.to_string())?;
+        match module {
+            Some(module) => {
+                self.modules
+                    .write()
+                    .unwrap()
+                    .insert(module.to_string(), level);
+            }
+            None => *self.default.write().unwrap() = level,
+        }
+        Ok(())
+    }
+
+    pub fn reset(&self, module: &str) {
+        self.modules.write().unwrap().remove(module);
+    }
+
+    pub fn snapshot(&self) -> LogLevelsSnapshot {
+        LogLevelsSnapshot {
+            default: self.default.read().unwrap().to_string(),
+            modules: self
+                .modules
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(module, level)| (module.clone(), level.to_string()))
+                .collect(),
+        }
+    }
+}
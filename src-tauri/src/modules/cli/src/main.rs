@@ -0,0 +1,509 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use client::{
+    client_manage::ClientManage,
+    emitter::ClientScope,
+    sink::{ChannelSink, SinkEvent},
+    utils::{
+        CLIENT_IDENTIFICATION, CLIENT_IDENTIFICATION_ERROR, CLIENT_IDENTIFICATION_PUSH,
+        CLIENT_IDENTIFICATION_RESYNC,
+    },
+    w_client::{ClientOwner, WClient},
+};
+use proto::{descriptors::pool, message::Body, MessageBody};
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use profile::Profile;
+
+mod profile;
+
+/// 命令行客户端在 `ClientManage` 中使用的窗口名
+const CLI_WINDOW: &str = "cli";
+
+fn usage() -> String {
+    [
+        "usage: task_cli [--profile profile.json] [--host 127.0.0.1] [--port 9673] [--name name] [--descriptors set.pb] [--json] <command>",
+        "",
+        "commands:",
+        "  send <url> <json>        send a request and print the response, `--proto type` encodes the json as that message",
+        "  watch [event-pattern]    print pushes until ctrl-c, `*` matches any characters",
+        "  status                   connect and print the client and traffic stats",
+        "  ping [url] [--count 4]   measure request round trips, url defaults to /ping",
+    ]
+    .join("\n")
+}
+
+enum Command {
+    Send {
+        url: String,
+        data: Value,
+        proto: Option<String>,
+    },
+    Watch {
+        pattern: String,
+    },
+    Status,
+    Ping {
+        url: String,
+        count: u32,
+    },
+}
+
+struct Options {
+    profile: Profile,
+    json: bool,
+    command: Command,
+}
+
+/// `args` 不包括程序名
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Options> {
+    let mut profile = None;
+    let mut host = None;
+    let mut port = None;
+    let mut name = None;
+    let mut json = false;
+    let mut count = 4;
+    let mut proto = None;
+    let mut positional = vec![];
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let path = args.next().ok_or_else(|| anyhow!(usage()))?;
+                profile = Some(Profile::from_file(path)?);
+            }
+            "--host" => host = Some(args.next().ok_or_else(|| anyhow!(usage()))?),
+            "--port" => port = Some(args.next().ok_or_else(|| anyhow!(usage()))?.parse()?),
+            "--name" => name = Some(args.next().ok_or_else(|| anyhow!(usage()))?),
+            "--count" => count = args.next().ok_or_else(|| anyhow!(usage()))?.parse()?,
+            "--proto" => proto = Some(args.next().ok_or_else(|| anyhow!(usage()))?),
+            "--descriptors" => {
+                let path = args.next().ok_or_else(|| anyhow!(usage()))?;
+                pool().load_file(&path)?;
+            }
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{}", usage());
+                std::process::exit(0);
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    // 命令行参数覆盖配置文件
+    let mut profile = profile.unwrap_or_default();
+    if let Some(host) = host {
+        profile.ip = host;
+    }
+    if let Some(port) = port {
+        profile.port = port;
+    }
+    if name.is_some() {
+        profile.name = name;
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        Some("send") => {
+            let url = positional.next().ok_or_else(|| anyhow!(usage()))?;
+            let data = match positional.next() {
+                Some(data) => serde_json::from_str(&data)?,
+                None => json!({}),
+            };
+            Command::Send { url, data, proto }
+        }
+        Some("watch") => Command::Watch {
+            pattern: positional.next().unwrap_or_else(|| "*".to_string()),
+        },
+        Some("status") => Command::Status,
+        Some("ping") => Command::Ping {
+            url: positional.next().unwrap_or_else(|| "/ping".to_string()),
+            count,
+        },
+        _ => return Err(anyhow!(usage())),
+    };
+    Ok(Options {
+        profile,
+        json,
+        command,
+    })
+}
+
+/// 按 `--json` 输出一行 json 或者可读的文本
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print(&self, value: Value, text: String) {
+        if self.json {
+            println!("{}", value);
+        } else {
+            println!("{}", text);
+        }
+    }
+}
+
+fn event_name(event: &str) -> String {
+    format!("{}::{}", CLIENT_IDENTIFICATION, event)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => serde_json::to_string_pretty(value).unwrap_or_default(),
+    }
+}
+
+/// `*` 匹配任意长度的字符, 没有 `*` 时需要完全相同
+fn matches(pattern: &str, event: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == event;
+    }
+    let mut rest = match event.strip_prefix(parts[0]) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(parts[parts.len() - 1])
+}
+
+async fn connect(
+    manage: &ClientManage,
+    profile: &Profile,
+) -> Result<(WClient, UnboundedReceiver<SinkEvent>)> {
+    let (sink, events) = ChannelSink::new();
+    let client_id = manage
+        .add_client(
+            Arc::new(sink),
+            ClientOwner::new(CLI_WINDOW),
+            profile.ip.clone(),
+            profile.port,
+            profile.name.clone(),
+            ClientScope::Window,
+        )
+        .await?;
+    let client = manage
+        .get_client(client_id)
+        .ok_or_else(|| anyhow!("client not found"))?;
+    Ok((client, events))
+}
+
+async fn send(
+    mut client: WClient,
+    out: &Output,
+    url: String,
+    data: Value,
+    proto: Option<String>,
+) -> bool {
+    let body = match proto {
+        Some(proto) => match Body::from_message(&proto, &data) {
+            Ok(body) => body,
+            Err(error) => {
+                out.print(
+                    json!({ "url": url, "ok": false, "error": error.to_string() }),
+                    format!("error: {}", error),
+                );
+                return false;
+            }
+        },
+        None => Body::from_serialize(data),
+    };
+    match client.request(url.clone(), body).await {
+        Ok(data) => {
+            out.print(json!({ "url": url, "ok": true, "data": data }), text(&data));
+            true
+        }
+        Err(error) => {
+            out.print(
+                json!({ "url": url, "ok": false, "error": error }),
+                format!("error: {}", text(&error)),
+            );
+            false
+        }
+    }
+}
+
+async fn watch(
+    client: WClient,
+    mut events: UnboundedReceiver<SinkEvent>,
+    out: &Output,
+    pattern: String,
+) -> bool {
+    let push_event = event_name(CLIENT_IDENTIFICATION_PUSH);
+    let resync_event = event_name(CLIENT_IDENTIFICATION_RESYNC);
+    let error_event = event_name(CLIENT_IDENTIFICATION_ERROR);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Some(event) => event,
+                None => return true,
+            },
+            _ = &mut ctrl_c => return true,
+        };
+        let payload = event.payload;
+        if event.event == push_event {
+            let name = payload["event"].as_str().unwrap_or_default();
+            if !matches(&pattern, name) {
+                continue;
+            }
+            out.print(
+                json!({ "kind": "push", "push": payload }),
+                format!(
+                    "{} #{} {}",
+                    name,
+                    payload["seq"],
+                    serde_json::to_string(&payload["data"]).unwrap_or_default()
+                ),
+            );
+            // 输出之后就算处理完成, 需要确认的推送在这里确认
+            let id = payload["id"].as_str().unwrap_or_default();
+            if payload["ack"].as_bool().unwrap_or(false) && !id.is_empty() {
+                client.ack(&[id.to_string()]);
+            }
+        } else if event.event == resync_event {
+            out.print(
+                json!({ "kind": "resync", "resync": payload }),
+                format!(
+                    "resync after {}#{}: {}",
+                    text(&payload["session"]),
+                    payload["after"],
+                    text(&payload["reason"])
+                ),
+            );
+        } else if event.event == error_event {
+            out.print(
+                json!({ "kind": "error", "error": payload }),
+                format!("error: {}", text(&payload)),
+            );
+        }
+    }
+}
+
+fn status(manage: &ClientManage, client: &WClient, out: &Output, connect_time: Duration) {
+    let metrics = manage.metrics().remove(&client.address);
+    let connect_ms = connect_time.as_secs_f64() * 1000.0;
+    let mut lines = vec![
+        format!("address   {}", client.address),
+        format!("state     connected in {:.1}ms", connect_ms),
+        format!("client    {}", client.client_id),
+    ];
+    if let Some(metrics) = &metrics {
+        lines.push(format!(
+            "frames    {} in, {} out, {} invalid",
+            metrics.frames_in, metrics.frames_out, metrics.invalid_frames
+        ));
+        lines.push(format!(
+            "bytes     {} in, {} out",
+            metrics.bytes_in, metrics.bytes_out
+        ));
+        if let Some(clock) = &metrics.clock {
+            lines.push(format!(
+                "clock     offset {:.1}ms, rtt {:.1}ms ({} samples)",
+                clock.offset_ms, clock.rtt_ms, clock.samples
+            ));
+        }
+    }
+    out.print(
+        json!({
+            "address": client.address,
+            "connected": true,
+            "connectMs": connect_ms,
+            "client": client.info(),
+            "metrics": metrics,
+        }),
+        lines.join("\n"),
+    );
+}
+
+async fn ping(client: WClient, out: &Output, url: String, count: u32) -> bool {
+    let mut received = 0;
+    let mut total = 0.0;
+    for seq in 0..count {
+        if seq > 0 {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        let start = Instant::now();
+        let res = client
+            .clone()
+            .request_response(url.clone(), Body::from_serialize(json!({})))
+            .await;
+        let time = start.elapsed().as_secs_f64() * 1000.0;
+        let (ok, response) = match res {
+            Ok(response) => (true, response),
+            Err(response) => (false, response),
+        };
+        let data = response.data.json_value();
+        // 超时和发送失败的响应是本地生成的, 没有状态码; 服务端返回的错误状态也算收到了回复
+        if response.status.is_none() {
+            out.print(
+                json!({ "seq": seq, "url": url, "reply": false, "error": data }),
+                format!("no reply from {}{}: {}", client.address, url, text(&data)),
+            );
+            continue;
+        }
+        received += 1;
+        total += time;
+        out.print(
+            json!({ "seq": seq, "url": url, "reply": true, "ok": ok, "timeMs": time, "data": data }),
+            format!(
+                "reply from {}{}: time={:.1}ms{}",
+                client.address,
+                url,
+                time,
+                if ok { String::new() } else { format!(" error={}", text(&data)) }
+            ),
+        );
+    }
+    if !out.json {
+        let average = if received > 0 {
+            total / received as f64
+        } else {
+            0.0
+        };
+        println!(
+            "{} sent, {} received, avg {:.1}ms",
+            count, received, average
+        );
+    }
+    received > 0
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let options = parse_args(std::env::args().skip(1))?;
+    let out = Output { json: options.json };
+    let manage = ClientManage::new();
+
+    let start = Instant::now();
+    let (client, events) = match connect(&manage, &options.profile).await {
+        Ok(connected) => connected,
+        Err(error) => {
+            out.print(
+                json!({ "address": options.profile.address(), "connected": false, "error": error.to_string() }),
+                format!("connect {} error: {}", options.profile.address(), error),
+            );
+            std::process::exit(1);
+        }
+    };
+    let connect_time = start.elapsed();
+
+    let success = match options.command {
+        Command::Send { url, data, proto } => send(client, &out, url, data, proto).await,
+        Command::Watch { pattern } => watch(client, events, &out, pattern).await,
+        Command::Status => {
+            status(&manage, &client, &out, connect_time);
+            true
+        }
+        Command::Ping { url, count } => ping(client, &out, url, count).await,
+    };
+
+    manage.close_all().await?;
+    if !success {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn wildcard_patterns() {
+        assert!(matches("task/done", "task/done"));
+        assert!(!matches("task/done", "task/done/1"));
+        assert!(matches("*", "task/done"));
+        assert!(matches("*", ""));
+        // 开头, 结尾和中间的 `*`
+        assert!(matches("*/done", "task/done"));
+        assert!(!matches("*/done", "task/done/1"));
+        assert!(matches("task/*", "task/"));
+        assert!(!matches("task/*", "tasks/done"));
+        assert!(matches("task/*/done", "task/1/done"));
+        assert!(matches("task/*/done", "task//done"));
+        assert!(!matches("task/*/done", "task/done"));
+        assert!(matches("*task*", "my/task/1"));
+        assert!(matches("a*b*c", "a-c-b-c"));
+        assert!(!matches("ab*bc", "abc"));
+        // 空的模式只匹配空的事件
+        assert!(matches("", ""));
+        assert!(!matches("", "task"));
+    }
+
+    #[test]
+    fn flags_override_profile() {
+        let path =
+            std::env::temp_dir().join(format!("task_cli_profile_{}.json", std::process::id()));
+        fs::write(&path, r#"{"ip":"10.0.0.1","port":9000,"name":"lab"}"#).unwrap();
+        let profile = path.to_str().unwrap();
+
+        let options = parse(&["--profile", profile, "status"]).unwrap();
+        assert_eq!(options.profile.address(), "10.0.0.1:9000");
+        assert_eq!(options.profile.name.as_deref(), Some("lab"));
+
+        // 与参数的先后顺序无关
+        let options = parse(&[
+            "--port",
+            "1",
+            "--profile",
+            profile,
+            "--name",
+            "cli",
+            "status",
+        ])
+        .unwrap();
+        assert_eq!(options.profile.address(), "10.0.0.1:1");
+        assert_eq!(options.profile.name.as_deref(), Some("cli"));
+        let options = parse(&["--profile", profile, "--host", "localhost", "status"]).unwrap();
+        assert_eq!(options.profile.address(), "localhost:9000");
+
+        fs::write(&path, r#"{"ip":"10.0.0.1","token":"x"}"#).unwrap();
+        assert!(parse(&["--profile", profile, "status"]).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn commands() {
+        let options = parse(&["--json", "send", "/echo", r#"{"a":1}"#]).unwrap();
+        assert!(options.json);
+        assert_eq!(options.profile.address(), "127.0.0.1:9673");
+        assert!(matches!(
+            options.command,
+            Command::Send { url, data, proto: None } if url == "/echo" && data == json!({ "a": 1 })
+        ));
+        assert!(matches!(
+            parse(&["watch"]).unwrap().command,
+            Command::Watch { pattern } if pattern == "*"
+        ));
+        assert!(matches!(
+            parse(&["ping", "--count", "2"]).unwrap().command,
+            Command::Ping { url, count: 2 } if url == "/ping"
+        ));
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["send"]).is_err());
+        assert!(parse(&["--port"]).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// 命令行的连接参数, 只是 connect 命令的 `ip`, `port` 和 `name`, 省得每次都写 `--host` / `--port`.
+/// 桌面端没有保存连接配置的文件, 这里不是共享的配置; 协议本身没有认证, 所以也没有账号或者 token.
+/// 不认识的字段直接报错, 避免以为写进去的配置生效了
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Profile {
+    pub ip: String,
+    pub port: u16,
+    /// 同一地址建立多个连接时用名称区分
+    pub name: Option<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            ip: "127.0.0.1".to_string(),
+            // 与页面的 APP_API_DEFAULT_PORT 相同
+            port: 9673,
+            name: None,
+        }
+    }
+}
+
+impl Profile {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text =
+            fs::read_to_string(path).with_context(|| format!("read profile {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parse profile {}", path.display()))
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
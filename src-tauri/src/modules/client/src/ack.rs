@@ -0,0 +1,158 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+};
+
+use proto::message::Push;
+
+/// 最多保留的未确认推送
+pub const MAX_PENDING_ACKS: usize = 1024;
+/// 记住最近确认过的推送 id, 用来丢弃服务端的重复投递
+pub const ACKED_HISTORY: usize = 4096;
+
+/// 收到一条需要确认的推送之后的处理方式
+#[derive(Debug, PartialEq, Eq)]
+pub enum Delivery {
+    /// 第一次收到, 发给页面并等待确认
+    Deliver,
+    /// 已经在等待页面确认
+    Pending,
+    /// 页面已经确认过, 服务端没有收到确认又重发了
+    Acked,
+}
+
+#[derive(Default)]
+struct AckState {
+    order: VecDeque<String>,
+    pending: HashMap<String, Push>,
+    acked_order: VecDeque<String>,
+    acked: HashSet<String>,
+}
+
+/// 需要确认的推送在页面确认之前保存在这里, 页面重新加载后可以再次发送
+#[derive(Default)]
+pub struct AckBuffer {
+    state: Mutex<AckState>,
+}
+
+impl AckBuffer {
+    /// 记录收到的推送, 超过容量时丢弃最早的一条并返回它
+    pub fn receive(&self, push: &Push) -> (Delivery, Option<Push>) {
+        let mut state = self.state.lock().unwrap();
+        if state.acked.contains(&push.id) {
+            return (Delivery::Acked, None);
+        }
+        if state.pending.contains_key(&push.id) {
+            return (Delivery::Pending, None);
+        }
+        state.order.push_back(push.id.clone());
+        state.pending.insert(push.id.clone(), push.clone());
+        let mut evicted = None;
+        while state.order.len() > MAX_PENDING_ACKS {
+            if let Some(id) = state.order.pop_front() {
+                evicted = state.pending.remove(&id);
+            }
+        }
+        (Delivery::Deliver, evicted)
+    }
+
+    /// 页面确认处理完成, 返回之前确实在等待确认的 id
+    pub fn ack(&self, ids: &[String]) -> Vec<String> {
+        let mut state = self.state.lock().unwrap();
+        let mut acked = vec![];
+        for id in ids {
+            if state.pending.remove(id).is_none() {
+                continue;
+            }
+            state.order.retain(|pending| pending != id);
+            if state.acked.insert(id.clone()) {
+                state.acked_order.push_back(id.clone());
+            }
+            acked.push(id.clone());
+        }
+        while state.acked_order.len() > ACKED_HISTORY {
+            if let Some(id) = state.acked_order.pop_front() {
+                state.acked.remove(&id);
+            }
+        }
+        acked
+    }
+
+    /// 按收到的顺序返回所有未确认的推送
+    pub fn pending(&self) -> Vec<Push> {
+        let state = self.state.lock().unwrap();
+        state
+            .order
+            .iter()
+            .filter_map(|id| state.pending.get(id).cloned())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(id: &str) -> Push {
+        let mut push = Push::new();
+        push.id = id.to_string();
+        push.ack = true;
+        push
+    }
+
+    fn ids(pushes: &[Push]) -> Vec<String> {
+        pushes.iter().map(|push| push.id.clone()).collect()
+    }
+
+    #[test]
+    fn deliver_once_until_acked() {
+        let buffer = AckBuffer::default();
+        assert_eq!(buffer.receive(&push("a")), (Delivery::Deliver, None));
+        assert_eq!(buffer.receive(&push("b")), (Delivery::Deliver, None));
+        assert_eq!(buffer.receive(&push("a")), (Delivery::Pending, None));
+        assert_eq!(ids(&buffer.pending()), vec!["a", "b"]);
+
+        assert_eq!(buffer.ack(&["a".to_string(), "x".to_string()]), vec!["a"]);
+        assert_eq!(ids(&buffer.pending()), vec!["b"]);
+        // 服务端重发已经确认的推送
+        assert_eq!(buffer.receive(&push("a")), (Delivery::Acked, None));
+        // 重复确认没有效果
+        assert!(buffer.ack(&["a".to_string()]).is_empty());
+        assert_eq!(buffer.len(), 1);
+    }
+
+    #[test]
+    fn evict_oldest_when_full() {
+        let buffer = AckBuffer::default();
+        for index in 0..MAX_PENDING_ACKS {
+            buffer.receive(&push(&index.to_string()));
+        }
+        let (delivery, evicted) = buffer.receive(&push("last"));
+        assert_eq!(delivery, Delivery::Deliver);
+        assert_eq!(evicted.map(|push| push.id), Some("0".to_string()));
+        assert_eq!(buffer.len(), MAX_PENDING_ACKS);
+        assert_eq!(buffer.pending().first().unwrap().id, "1");
+    }
+
+    #[test]
+    fn acked_history_is_bounded() {
+        let buffer = AckBuffer::default();
+        for index in 0..=ACKED_HISTORY {
+            let id = index.to_string();
+            buffer.receive(&push(&id));
+            buffer.ack(&[id]);
+        }
+        assert!(buffer.is_empty());
+        // 最早的确认已经被忘记, 再收到时当作新的推送
+        assert_eq!(buffer.receive(&push("0")).0, Delivery::Deliver);
+        assert_eq!(buffer.receive(&push("1")).0, Delivery::Acked);
+    }
+}
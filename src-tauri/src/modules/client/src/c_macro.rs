@@ -3,7 +3,7 @@ macro_rules! wrap_event_err {
     ($trigger:expr, $event:expr, $data:expr) => {
         if let Err(error) = $trigger.emit(&format!("{}::{}", CLIENT_IDENTIFICATION, $event), $data)
         {
-            error!("emit error: {:?}", error);
+            error!("emit {} error: {:?}", $event, error);
         }
     };
 }
@@ -18,9 +18,7 @@ macro_rules! handle_message {
                     .map_err(|error| ConnError::LockError(error.to_string()))
                     .unwrap();
                 for client in recv_client.read().unwrap().iter_mut() {
-                    if (client.address == recv_conn.get_address()) {
-                    
-                    }
+                    if (client.address == recv_conn.get_address()) {}
                 }
             }
             Err(error) => {
@@ -0,0 +1,685 @@
+use anyhow::Result;
+use conn::{ConnBuilder, ConnBuilderConfig, ConnectError, Connection, Protocol};
+use dashmap::{mapref::entry::Entry, DashMap};
+use proto::{
+    descriptors::pool,
+    message::{Body, Push},
+    MessageBody,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::{
+    sync::{broadcast, watch, OnceCell},
+    task::JoinHandle,
+};
+
+use crate::{
+    ctx_error, ctx_info, ctx_warn,
+    emitter::{ClientScope, Emitter},
+    error::ConnError,
+    inbound::{InboundConfig, InboundQueue},
+    inspector::{InspectorEntry, InspectorFilter},
+    logger::LogContext,
+    metrics::{ConnectionSnapshot, Metrics},
+    recorder::RecordOptions,
+    sequence::{PushCursor, ResumeFrom, ResyncNotice},
+    sink::EventSink,
+    traffic::{Direction, Traffic},
+    w_client::{ClientInfo, ClientKey, ClientOwner, RecvData, WClient, DESCRIPTORS_URL},
+};
+
+/// 请求服务端重发推送的地址
+const PUSH_RESUME_URL: &str = "/push/resume";
+
+/// 连接状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnState {
+    Connecting,
+    Connected,
+    /// 连接断开, 正在重连
+    Reconnecting,
+    /// 连接断开且不会再重连
+    Disconnected,
+    /// 主动关闭
+    Closed,
+}
+
+/// 订阅某个地址的客户端, 消息进入自己的队列后由单个任务按顺序处理
+struct Subscriber {
+    client: WClient,
+    queue: Arc<InboundQueue>,
+    handle: JoinHandle<()>,
+}
+
+/// 单个服务端地址的连接状态, 同一地址的所有客户端共享
+struct Endpoint {
+    // 并发 connect 同一地址时只有一个会真正建立连接, 其他的等待结果
+    conn: OnceCell<Connection>,
+    // 连接已经被主动关闭, 读取任务应当退出
+    closed: AtomicBool,
+    // client_id -> 该客户端的订阅者
+    subscribers: DashMap<String, Subscriber>,
+    // 推送序号, 用来发现断线期间漏掉的推送
+    cursor: Mutex<PushCursor>,
+    state: watch::Sender<ConnState>,
+    // 已经向服务端获取过 proto 描述
+    descriptors: AtomicBool,
+}
+
+impl Endpoint {
+    fn new() -> Self {
+        Self {
+            conn: OnceCell::new(),
+            closed: AtomicBool::new(false),
+            subscribers: DashMap::new(),
+            cursor: Mutex::new(PushCursor::default()),
+            state: watch::channel(ConnState::Connecting).0,
+            descriptors: AtomicBool::new(false),
+        }
+    }
+
+    fn set_state(&self, state: ConnState) {
+        self.state.send_if_modified(|current| {
+            // 主动关闭之后不再变化
+            if *current == state || *current == ConnState::Closed {
+                return false;
+            }
+            *current = state;
+            true
+        });
+    }
+
+    /// 订阅该地址的消息, 订阅之前收到的消息不会补发
+    fn attach(&self, client: WClient, config: InboundConfig) {
+        let queue = Arc::new(InboundQueue::new(config));
+        let client_id = client.client_id.clone();
+        let mut consumer_client = client.clone();
+        let consumer = queue.clone();
+        let handle = tokio::spawn(async move {
+            while let Some(data) = consumer.pop().await {
+                consumer_client.handle_message(&data).await;
+            }
+        });
+        self.subscribers.insert(
+            client_id,
+            Subscriber {
+                client,
+                queue,
+                handle,
+            },
+        );
+    }
+
+    fn detach(&self, client_id: &str) {
+        if let Some((_, subscriber)) = self.subscribers.remove(client_id) {
+            subscriber.queue.close();
+            subscriber.handle.abort();
+        }
+    }
+
+    /// 收到的消息只解码一次, 以 Arc 分发给该地址的所有客户端
+    fn dispatch(&self, address: &str, data: RecvData) {
+        let data = Arc::new(data);
+        for subscriber in self.subscribers.iter() {
+            let queue = &subscriber.value().queue;
+            if let Some(dropped) = queue.push(data.clone()) {
+                ctx_warn!(
+                    LogContext::new()
+                        .address(address)
+                        .client_id(subscriber.key()),
+                    "inbound queue full, drop {} message, {} dropped in total",
+                    dropped.kind(),
+                    queue.dropped()
+                );
+            }
+        }
+    }
+
+    /// 推送先按序号排好再分发, 缺号时向服务端请求重发
+    fn dispatch_push(self: &Arc<Self>, address: &str, push: Push) {
+        let resume = {
+            let mut cursor = self.cursor.lock().unwrap();
+            let (ready, resume) = cursor.accept(push);
+            for push in ready {
+                self.dispatch(address, RecvData::Push(push));
+            }
+            resume
+        };
+        if let Some(resume) = resume {
+            self.resume(address, resume);
+        }
+    }
+
+    fn resume(self: &Arc<Self>, address: &str, resume: ResumeFrom) {
+        let client = self
+            .subscribers
+            .iter()
+            .next()
+            .map(|subscriber| subscriber.client.clone());
+        let mut client = match client {
+            Some(client) => client,
+            None => return,
+        };
+        let endpoint = self.clone();
+        let address = address.to_string();
+        tokio::spawn(async move {
+            ctx_info!(
+                LogContext::new().address(&address),
+                "resume push after {}#{}",
+                resume.session,
+                resume.after
+            );
+            let res = client
+                .request(
+                    PUSH_RESUME_URL.to_string(),
+                    Body::from_serialize(serde_json::to_value(&resume).unwrap_or_default()),
+                )
+                .await;
+            // 服务端在响应之前重发推送, 收到响应时仍然缺号说明无法补齐
+            // 在锁内分发, 保证排在读取任务之后收到的推送前面
+            let (reason, next) = {
+                let mut cursor = endpoint.cursor.lock().unwrap();
+                let (ready, reason, next) = match res {
+                    Ok(_) => {
+                        let resumed = cursor.resumed(&resume);
+                        let reason = if resumed.gap {
+                            Some("gap not filled by server".to_string())
+                        } else {
+                            None
+                        };
+                        (resumed.ready, reason, resumed.next)
+                    }
+                    Err(error) => {
+                        let reason = match error {
+                            Value::String(error) => error,
+                            error => error.to_string(),
+                        };
+                        (cursor.give_up(), Some(reason), None)
+                    }
+                };
+                for push in ready {
+                    endpoint.dispatch(&address, RecvData::Push(push));
+                }
+                (reason, next)
+            };
+            if let Some(next) = next {
+                endpoint.resume(&address, next);
+            }
+            let reason = match reason {
+                Some(reason) => reason,
+                None => return,
+            };
+            endpoint.dispatch(
+                &address,
+                RecvData::Resync(ResyncNotice {
+                    session: resume.session,
+                    after: resume.after,
+                    reason,
+                }),
+            );
+        });
+    }
+
+    async fn close(&self, address: &str) {
+        self.closed.store(true, Ordering::Release);
+        self.set_state(ConnState::Closed);
+        for subscriber in self.subscribers.iter() {
+            subscriber.value().queue.close();
+            subscriber.value().handle.abort();
+        }
+        self.subscribers.clear();
+        if let Some(conn) = self.conn.get() {
+            if let Err(error) = conn.clone().disconnect().await {
+                ctx_error!(
+                    LogContext::new().address(address),
+                    "disconnect error: {}",
+                    error
+                );
+            }
+        }
+    }
+}
+
+/// 管理所有客户端, 同一地址的客户端共享一个连接.
+/// 需要在 tokio 运行时中调用
+pub struct ClientManage {
+    clients: DashMap<String, WClient>,
+    // (窗口, 地址, 名称) -> client_id
+    w_c: DashMap<ClientKey, String>,
+    // client_id -> 最近一次 connect 时的页面标识
+    pages: DashMap<String, String>,
+    endpoints: Arc<DashMap<String, Arc<Endpoint>>>,
+    traffic: Arc<Traffic>,
+    inbound: InboundConfig,
+}
+
+impl Default for ClientManage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientManage {
+    pub fn new() -> Self {
+        Self::with_inbound(InboundConfig::default())
+    }
+
+    /// 指定每个客户端入站队列的容量和溢出策略
+    pub fn with_inbound(inbound: InboundConfig) -> Self {
+        Self {
+            clients: DashMap::new(),
+            w_c: DashMap::new(),
+            pages: DashMap::new(),
+            endpoints: Arc::new(DashMap::new()),
+            traffic: Arc::new(Traffic::default()),
+            inbound,
+        }
+    }
+
+    /// 同一个窗口对同一个地址的同名连接只会创建一个客户端, 再次 connect 时返回原来的客户端.
+    /// 只有 `owner.page` 与上次不同 (页面重新加载) 时才清理旧页面留下的请求, 订阅和推送策略,
+    /// 同一个页面的多个组件或者 rust 中的调用方重复 connect 没有副作用.
+    /// on_page_load 在新页面的脚本执行之后才触发, 不能用来判断.
+    /// 客户端的事件通过 `sink` 发出
+    pub async fn add_client(
+        &self,
+        sink: Arc<dyn EventSink>,
+        owner: ClientOwner,
+        ip: String,
+        port: u16,
+        name: Option<String>,
+        scope: ClientScope,
+    ) -> Result<String> {
+        let address = format!("{}:{}", ip.clone(), port);
+        let key = ClientKey {
+            window: owner.window.clone(),
+            address: address.clone(),
+            name: name.clone(),
+        };
+        if let Some(client) = self.find_client(&key) {
+            if self.page_changed(&client.client_id, owner.page) {
+                ctx_info!(client.log_ctx(), "window reloaded, reset client");
+                client.reset("window reloaded");
+            }
+            return Ok(client.client_id.clone());
+        }
+
+        let (endpoint, client) = loop {
+            // 先把 Endpoint 取出来, 不要在持有 DashMap 的锁时 await
+            let endpoint = self
+                .endpoints
+                .entry(address.clone())
+                .or_insert_with(|| Arc::new(Endpoint::new()))
+                .clone();
+
+            let conn = endpoint
+                .conn
+                .get_or_try_init(|| async { self.dial(&ip, port, endpoint.clone()).await })
+                .await?
+                .clone();
+
+            let emitter = Emitter::new(sink.clone(), &owner.window, scope.clone());
+            let client = WClient::build(
+                emitter,
+                ip.clone(),
+                port,
+                name.clone(),
+                conn,
+                self.traffic.clone(),
+            );
+            let client_id = client.client_id.clone();
+            // 先放进 clients 再公开 id, 拿到 id 的调用方一定能找到客户端
+            self.clients.insert(client_id.clone(), client.clone());
+            let entry = match self.w_c.entry(key.clone()) {
+                // 并发 connect 时只保留先完成的客户端
+                Entry::Occupied(entry) => {
+                    let winner = entry.get().clone();
+                    drop(entry);
+                    self.clients.remove(&client_id);
+                    return Ok(winner);
+                }
+                Entry::Vacant(entry) => entry,
+            };
+            if !self.attach_endpoint(&address, &endpoint, &client) {
+                // 等待连接期间最后一个客户端断开, 连接已经关闭, 重新建立
+                drop(entry);
+                self.clients.remove(&client_id);
+                continue;
+            }
+            if let Some(page) = owner.page.clone() {
+                self.pages.insert(client_id.clone(), page);
+            }
+            entry.insert(client_id);
+            break (endpoint, client);
+        };
+        let client_id = client.client_id.clone();
+        // 每个连接只获取一次服务端的 proto 描述, 服务端不支持时忽略
+        if !endpoint.descriptors.swap(true, Ordering::AcqRel) {
+            let mut client = client.clone();
+            tokio::spawn(async move {
+                match client.fetch_descriptors().await {
+                    Ok(names) => ctx_info!(
+                        client.log_ctx(),
+                        "load {} message types from server",
+                        names.len()
+                    ),
+                    Err(error) => ctx_info!(
+                        client.log_ctx().url(DESCRIPTORS_URL),
+                        "no descriptors from server: {}",
+                        error
+                    ),
+                }
+            });
+        }
+        Ok(client_id)
+    }
+
+    /// 在 endpoints 的锁内订阅, 与 `release_endpoint` 互斥.
+    /// endpoint 已经被移除或关闭时返回 false
+    fn attach_endpoint(&self, address: &str, endpoint: &Arc<Endpoint>, client: &WClient) -> bool {
+        let current = match self.endpoints.get(address) {
+            Some(current) => current,
+            None => return false,
+        };
+        if !Arc::ptr_eq(current.value(), endpoint) || endpoint.closed.load(Ordering::Acquire) {
+            return false;
+        }
+        endpoint.attach(client.clone(), self.inbound);
+        true
+    }
+
+    /// 取消客户端的订阅, 地址上没有其他客户端时移除并关闭连接
+    fn release_endpoint(&self, address: &str, client_id: &str) {
+        let endpoint = match self.endpoints.get(address) {
+            Some(endpoint) => endpoint.value().clone(),
+            None => return,
+        };
+        endpoint.detach(client_id);
+        // 在 endpoints 的锁内判断, 不会和正在订阅的 connect 交错
+        let removed = self.endpoints.remove_if(address, |_, current| {
+            Arc::ptr_eq(current, &endpoint) && current.subscribers.is_empty()
+        });
+        if removed.is_some() {
+            endpoint.closed.store(true, Ordering::Release);
+            let address = address.to_string();
+            tokio::spawn(async move {
+                endpoint.close(&address).await;
+            });
+        }
+    }
+
+    /// 记录客户端最新的页面标识, 与上次记录的不同时返回 true.
+    /// 没有标识的调用方不会触发清理
+    fn page_changed(&self, client_id: &str, page: Option<String>) -> bool {
+        let page = match page {
+            Some(page) => page,
+            None => return false,
+        };
+        match self.pages.insert(client_id.to_string(), page.clone()) {
+            Some(previous) => previous != page,
+            None => false,
+        }
+    }
+
+    fn find_client(&self, key: &ClientKey) -> Option<WClient> {
+        let client_id = self.w_c.get(key)?.value().clone();
+        self.get_client(client_id)
+    }
+
+    /// 窗口能收到事件的所有客户端
+    pub fn list_clients(&self, label: &str) -> Vec<ClientInfo> {
+        self.clients
+            .iter()
+            .filter(|client| client.emitter.targets(label))
+            .map(|client| client.info())
+            .collect()
+    }
+
+    /// 建立连接并开启读取任务, 收到的消息广播给 endpoint 的订阅者
+    async fn dial(&self, ip: &str, port: u16, endpoint: Arc<Endpoint>) -> Result<Connection> {
+        // 回调由连接持有, 用 Weak 避免和 Endpoint 形成循环引用
+        let error_endpoint = Arc::downgrade(&endpoint);
+        let address = format!("{}:{}", ip, port);
+        let connect_opt = ConnBuilderConfig {
+            host: ip.to_string(),
+            port,
+            protocol: Protocol::WEBSOCKET,
+            error_callback: Box::new(move |err: ConnectError| {
+                let state = match err {
+                    ConnectError::Reconnecting => Some(ConnState::Reconnecting),
+                    ConnectError::Disconnect(_)
+                    | ConnectError::ConnectionClosed(_)
+                    | ConnectError::ReconnectFailed => Some(ConnState::Disconnected),
+                    _ => None,
+                };
+                let err = match err {
+                    ConnectError::Disconnect(err)
+                    | ConnectError::SendError(err)
+                    | ConnectError::ConnectionError(err)
+                    | ConnectError::RecvError(err)
+                    | ConnectError::Unknown(err)
+                    | ConnectError::ConnectionClosed(err) => err,
+                    ConnectError::Connection(err) => err.to_string(),
+                    ConnectError::ConnectionTimeout => String::from("connection timeout"),
+                    ConnectError::ConnectionRefused => String::from("connection refused"),
+                    ConnectError::ConnectionReset => String::from("connection reset"),
+                    ConnectError::Reconnecting => String::from("connection reconnecting"),
+                    ConnectError::ReconnectFailed => String::from("connection reconnect failed"),
+                };
+                if let Some(endpoint) = error_endpoint.upgrade() {
+                    if let Some(state) = state {
+                        endpoint.set_state(state);
+                    }
+                    endpoint.dispatch(&address, RecvData::Error(err));
+                }
+            }),
+        };
+        let mut conn = ConnBuilder::new(connect_opt).build();
+        conn.connect().await?;
+        endpoint.set_state(ConnState::Connected);
+        let traffic = self.traffic.clone();
+        let mut r_conn = conn.clone();
+        // 开启任务读取数据
+        tokio::spawn(async move {
+            loop {
+                let payload = r_conn.receive().await;
+                match payload {
+                    Ok(payload) => {
+                        // 重连成功后第一次收到数据
+                        endpoint.set_state(ConnState::Connected);
+                        let address = r_conn.get_address();
+                        let decoded = RecvData::decode(&payload);
+                        traffic.observe(Direction::Recv, &address, &payload, &decoded);
+                        match decoded {
+                            Ok(RecvData::Push(push)) => endpoint.dispatch_push(&address, push),
+                            Ok(data) => endpoint.dispatch(&address, data),
+                            Err(error) => {
+                                ctx_error!(
+                                    LogContext::new().address(&address),
+                                    "{}, {} bytes: {}",
+                                    error,
+                                    payload.len(),
+                                    hex::encode(&payload)
+                                );
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        if endpoint.closed.load(Ordering::Acquire) {
+                            break;
+                        }
+                        ctx_error!(
+                            LogContext::new().address(&r_conn.get_address()),
+                            "receive error: {}",
+                            error
+                        );
+                    }
+                }
+            }
+        });
+        Ok(conn)
+    }
+
+    /// 所有客户端
+    pub fn all_clients(&self) -> Vec<ClientInfo> {
+        self.clients.iter().map(|client| client.info()).collect()
+    }
+
+    /// 客户端所在连接的状态, 可以等待状态变化
+    pub fn conn_state(&self, client_id: &str) -> Option<watch::Receiver<ConnState>> {
+        let address = self.clients.get(client_id)?.address.clone();
+        let endpoint = self.endpoints.get(&address)?;
+        Some(endpoint.state.subscribe())
+    }
+
+    pub fn get_client(&self, client_id: String) -> Option<WClient> {
+        self.clients
+            .get(&client_id)
+            .map(|client| client.value().clone())
+    }
+
+    pub fn remove_client(&self, client_id: String) -> Result<()> {
+        self.detach_client(&client_id, "client disconnected");
+        Ok(())
+    }
+
+    /// 移除客户端, 拒绝它还在等待的请求, 地址上没有其他客户端时关闭连接
+    fn detach_client(&self, client_id: &str, reason: &str) -> Option<WClient> {
+        let (_, client) = self.clients.remove(client_id)?;
+        self.pages.remove(client_id);
+        self.w_c
+            .remove_if(&client.key(), |_, current| current == client_id);
+        client.pushes.close();
+        client.store.clear();
+        self.release_endpoint(&client.address, client_id);
+        let rejected = client.clone();
+        let reason = reason.to_string();
+        tokio::spawn(async move {
+            rejected.reject_pending(&reason).await;
+        });
+        Some(client)
+    }
+
+    /// 让窗口接收已有客户端的事件
+    pub fn attach_window(&self, client_id: String, label: &str) -> Result<bool> {
+        let client = self
+            .get_client(client_id)
+            .ok_or_else(|| ConnError::NotFoundError("client".to_string()))?;
+        Ok(client.emitter.attach(label))
+    }
+
+    /// 窗口不再接收客户端的事件, 没有窗口接收时移除客户端
+    pub fn detach_window(&self, client_id: String, label: &str) -> Result<()> {
+        let client = self
+            .get_client(client_id.clone())
+            .ok_or_else(|| ConnError::NotFoundError("client".to_string()))?;
+        if !client.emitter.detach(label) {
+            self.detach_client(&client_id, "no window attached");
+        }
+        Ok(())
+    }
+
+    /// 窗口销毁后把它从所有客户端中移除, 只属于该窗口的客户端会被释放, 返回释放的数量.
+    /// app 作用域的客户端不属于任何窗口, 需要显式 disconnect
+    pub fn remove_window(&self, label: &str) -> usize {
+        // 先从每个客户端中移除窗口, 没有剩下窗口的客户端再释放
+        let mut client_ids = Vec::new();
+        for client in self.clients.iter() {
+            let remaining = client.emitter.detach(label);
+            if !remaining {
+                client_ids.push(client.key().clone());
+            }
+        }
+        for client_id in client_ids.iter() {
+            ctx_info!(
+                LogContext::new().client_id(client_id).window(label),
+                "window destroyed, remove client"
+            );
+            self.detach_client(client_id, "window destroyed");
+        }
+        client_ids.len()
+    }
+
+    pub fn start_record(&self, dir: PathBuf, options: RecordOptions) -> Result<PathBuf> {
+        self.traffic.recorder.start(dir, options)
+    }
+
+    pub fn stop_record(&self) {
+        self.traffic.recorder.stop();
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.traffic.recorder.is_recording()
+    }
+
+    pub fn inspector_query(&self, filter: &InspectorFilter) -> Vec<InspectorEntry> {
+        self.traffic.inspector.query(filter)
+    }
+
+    pub fn inspector_configure(&self, enabled: Option<bool>, capacity: Option<usize>) {
+        self.traffic.inspector.configure(enabled, capacity);
+    }
+
+    pub fn inspector_clear(&self, address: Option<&str>) {
+        self.traffic.inspector.clear(address);
+    }
+
+    pub fn inspector_subscribe(&self) -> broadcast::Receiver<InspectorEntry> {
+        self.traffic.inspector.subscribe()
+    }
+
+    /// 加载 FileDescriptorSet 文件, 返回其中的消息类型
+    pub fn load_descriptors(&self, path: impl AsRef<Path>) -> Result<Vec<String>> {
+        Ok(pool().load_file(path)?)
+    }
+
+    /// 运行时加载的消息类型
+    pub fn message_types(&self) -> Vec<String> {
+        pool().messages()
+    }
+
+    pub fn metrics(&self) -> HashMap<String, ConnectionSnapshot> {
+        self.traffic.metrics.snapshot()
+    }
+
+    pub fn metrics_prometheus(&self) -> String {
+        self.traffic.metrics.to_prometheus()
+    }
+
+    pub fn metrics_reset(&self) {
+        self.traffic.metrics.reset();
+    }
+
+    /// 统计的副本, 可以同时转换为快照和 Prometheus 文本, `reset` 为 true 时同时清空
+    pub fn metrics_collect(&self, reset: bool) -> Metrics {
+        self.traffic.metrics.collect(reset)
+    }
+
+    pub async fn close_all(&self) -> Result<()> {
+        for client in self.clients.iter() {
+            client.pushes.close();
+            client.store.clear();
+        }
+        self.clients.clear();
+        self.w_c.clear();
+        let endpoints: Vec<(String, Arc<Endpoint>)> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.key().clone(), endpoint.value().clone()))
+            .collect();
+        self.endpoints.clear();
+        for (address, endpoint) in endpoints {
+            endpoint.close(&address).await;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use serde::Serialize;
+
+/// 参与估算的最近样本数
+const CLOCK_WINDOW: usize = 16;
+
+/// 一次请求的时钟样本, 按 NTP 的方法由四个时间点 (unix 毫秒) 计算
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSample {
+    /// 服务端时钟 - 本地时钟
+    pub offset: f64,
+    /// 往返时间, 不包括服务端的处理时间
+    pub rtt: f64,
+}
+
+impl ClockSample {
+    pub fn new(
+        client_send: i64,
+        server_receive: i64,
+        server_send: i64,
+        client_receive: i64,
+    ) -> Self {
+        let (t1, t2, t3, t4) = (
+            client_send as f64,
+            server_receive as f64,
+            server_send as f64,
+            client_receive as f64,
+        );
+        Self {
+            offset: ((t2 - t1) + (t3 - t4)) / 2.0,
+            rtt: ((t4 - t1) - (t3 - t2)).max(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ClockSnapshot {
+    /// 服务端时钟比本地快的毫秒数, 服务端时间减去它就是本地时间
+    pub offset_ms: f64,
+    /// 估算使用的样本的往返时间, 毫秒
+    pub rtt_ms: f64,
+    /// 最近样本往返时间的平均值, 毫秒
+    pub rtt_mean_ms: f64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub samples: u64,
+}
+
+/// 按连接估算服务端的时钟偏差和往返时间.
+/// 往返时间越短的样本越准确, 取最近样本中往返时间最短的一个
+#[derive(Debug, Clone, Default)]
+pub struct ClockEstimator {
+    recent: VecDeque<ClockSample>,
+    samples: u64,
+}
+
+impl ClockEstimator {
+    pub fn observe(&mut self, sample: ClockSample) {
+        if self.recent.len() == CLOCK_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(sample);
+        self.samples += 1;
+    }
+
+    pub fn snapshot(&self) -> Option<ClockSnapshot> {
+        let best = self
+            .recent
+            .iter()
+            .min_by(|a, b| a.rtt.partial_cmp(&b.rtt).unwrap_or(Ordering::Equal))?;
+        Some(ClockSnapshot {
+            offset_ms: best.offset,
+            rtt_ms: best.rtt,
+            rtt_mean_ms: self.recent.iter().map(|sample| sample.rtt).sum::<f64>()
+                / self.recent.len() as f64,
+            samples: self.samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_offset_and_rtt() {
+        // 服务端快 100ms, 单程 10ms, 处理 5ms
+        let sample = ClockSample::new(1_000, 1_110, 1_115, 1_025);
+        assert_eq!(sample.offset, 100.0);
+        assert_eq!(sample.rtt, 20.0);
+        // 时间点不一致时往返时间不为负
+        assert_eq!(ClockSample::new(1_000, 1_000, 1_100, 1_010).rtt, 0.0);
+    }
+
+    #[test]
+    fn empty_estimator() {
+        assert!(ClockEstimator::default().snapshot().is_none());
+    }
+
+    #[test]
+    fn picks_fastest_recent_sample() {
+        let mut clock = ClockEstimator::default();
+        clock.observe(ClockSample {
+            offset: 50.0,
+            rtt: 40.0,
+        });
+        clock.observe(ClockSample {
+            offset: 30.0,
+            rtt: 10.0,
+        });
+        clock.observe(ClockSample {
+            offset: 80.0,
+            rtt: 70.0,
+        });
+        let snapshot = clock.snapshot().unwrap();
+        assert_eq!(snapshot.offset_ms, 30.0);
+        assert_eq!(snapshot.rtt_ms, 10.0);
+        assert_eq!(snapshot.rtt_mean_ms, 40.0);
+        assert_eq!(snapshot.samples, 3);
+    }
+
+    #[test]
+    fn old_samples_leave_the_window() {
+        let mut clock = ClockEstimator::default();
+        clock.observe(ClockSample {
+            offset: -5.0,
+            rtt: 1.0,
+        });
+        for _ in 0..CLOCK_WINDOW {
+            clock.observe(ClockSample {
+                offset: 20.0,
+                rtt: 8.0,
+            });
+        }
+        let snapshot = clock.snapshot().unwrap();
+        assert_eq!(snapshot.offset_ms, 20.0);
+        assert_eq!(snapshot.rtt_mean_ms, 8.0);
+        assert_eq!(snapshot.samples, CLOCK_WINDOW as u64 + 1);
+    }
+}
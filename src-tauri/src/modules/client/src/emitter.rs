@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::sink::EventSink;
+
+/// 客户端事件发往哪些窗口
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ClientScope {
+    /// 只发给创建客户端的窗口
+    #[default]
+    Window,
+    /// 发给所有窗口
+    App,
+    /// 发给一组窗口, 创建客户端的窗口总是包含在内
+    Group { windows: Vec<String> },
+}
+
+#[derive(Debug)]
+enum Targets {
+    App,
+    Windows(BTreeSet<String>),
+}
+
+/// 按客户端的作用域发送事件, 可以在运行时增减窗口
+pub struct Emitter {
+    sink: Arc<dyn EventSink>,
+    owner: String,
+    targets: RwLock<Targets>,
+}
+
+impl Emitter {
+    pub fn new(sink: Arc<dyn EventSink>, owner: &str, scope: ClientScope) -> Self {
+        let targets = match scope {
+            ClientScope::App => Targets::App,
+            ClientScope::Window => Targets::Windows(BTreeSet::from([owner.to_string()])),
+            ClientScope::Group { windows } => {
+                let mut windows: BTreeSet<String> = windows.into_iter().collect();
+                windows.insert(owner.to_string());
+                Targets::Windows(windows)
+            }
+        };
+        Self {
+            sink,
+            owner: owner.to_string(),
+            targets: RwLock::new(targets),
+        }
+    }
+
+    /// 与 `Window::emit` 相似的签名, 可以直接用在 `wrap_event_err!` 中
+    pub fn emit<S: Serialize>(&self, event: &str, payload: S) -> Result<(), String> {
+        let payload = serde_json::to_value(payload).map_err(|error| error.to_string())?;
+        let windows = match &*self.targets.read().unwrap() {
+            Targets::App => return self.sink.emit_all(event, &payload),
+            Targets::Windows(windows) => windows.clone(),
+        };
+        let mut result = Ok(());
+        for label in windows {
+            // 已经关闭的窗口由 sink 跳过, 由窗口销毁事件负责移除
+            if let Err(error) = self.sink.emit_to(&label, event, &payload) {
+                result = Err(error);
+            }
+        }
+        result
+    }
+
+    /// 创建客户端的窗口
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub fn scope(&self) -> ClientScope {
+        match &*self.targets.read().unwrap() {
+            Targets::App => ClientScope::App,
+            Targets::Windows(windows) if windows.len() == 1 && windows.contains(&self.owner) => {
+                ClientScope::Window
+            }
+            Targets::Windows(windows) => ClientScope::Group {
+                windows: windows.iter().cloned().collect(),
+            },
+        }
+    }
+
+    /// 事件是否会发到该窗口
+    pub fn targets(&self, label: &str) -> bool {
+        match &*self.targets.read().unwrap() {
+            Targets::App => true,
+            Targets::Windows(windows) => windows.contains(label),
+        }
+    }
+
+    /// 接收事件的窗口, app 作用域返回 None
+    pub fn windows(&self) -> Option<Vec<String>> {
+        match &*self.targets.read().unwrap() {
+            Targets::App => None,
+            Targets::Windows(windows) => Some(windows.iter().cloned().collect()),
+        }
+    }
+
+    /// 让窗口开始接收事件, app 作用域下所有窗口本来就会收到
+    pub fn attach(&self, label: &str) -> bool {
+        match &mut *self.targets.write().unwrap() {
+            Targets::App => false,
+            Targets::Windows(windows) => windows.insert(label.to_string()),
+        }
+    }
+
+    /// 窗口不再接收事件, 返回是否还有窗口在接收
+    pub fn detach(&self, label: &str) -> bool {
+        match &mut *self.targets.write().unwrap() {
+            Targets::App => true,
+            Targets::Windows(windows) => {
+                windows.remove(label);
+                !windows.is_empty()
+            }
+        }
+    }
+}
@@ -8,4 +8,6 @@ pub enum ConnError {
     SendError(String),
     #[error("lock error: {0}")]
     LockError(String),
-}
\ No newline at end of file
+    #[error("not found: {0}")]
+    NotFoundError(String),
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use super::w_client::RecvData;
+
+/// 默认每个客户端最多积压的消息数
+pub const DEFAULT_INBOUND_CAPACITY: usize = 1024;
+
+/// 队列满时的处理策略, 只作用于可以丢弃的推送 (没有序号且不需要确认).
+/// 响应, 带序号或需要确认的推送等消息不会被丢弃, 队列满时也会放入
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverflowPolicy {
+    /// 丢弃最早的同一事件的可丢弃推送, 保证页面拿到的是该事件最新的状态.
+    /// 没有同一事件的推送时丢弃新到的推送
+    #[default]
+    DropOldest,
+    /// 丢弃新到的可丢弃推送, 保留已经排队的消息
+    DropNewest,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboundConfig {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for InboundConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_INBOUND_CAPACITY,
+            policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+/// 没有序号且不需要确认的推送, 丢弃后由后续推送覆盖.
+/// 丢弃响应会让请求一直等到超时, 丢弃带序号或需要确认的推送会破坏缺号检测和确认
+fn droppable(data: &RecvData) -> bool {
+    match data {
+        RecvData::Push(push) => push.seq == 0 && !push.ack,
+        _ => false,
+    }
+}
+
+/// 单个客户端的入站队列, 由一个任务按到达顺序依次处理
+pub struct InboundQueue {
+    config: InboundConfig,
+    items: Mutex<VecDeque<Arc<RecvData>>>,
+    notify: Notify,
+    closed: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl InboundQueue {
+    pub fn new(config: InboundConfig) -> Self {
+        Self {
+            config: InboundConfig {
+                capacity: config.capacity.max(1),
+                policy: config.policy,
+            },
+            items: Mutex::new(VecDeque::new()),
+            notify: Notify::new(),
+            closed: AtomicBool::new(false),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// 放入一条消息, 队列满时按策略丢弃一条可丢弃的推送, 返回被丢弃的那条消息.
+    /// 只会用新推送替换同一事件的旧推送, 其它消息超出容量也会放入
+    pub fn push(&self, data: Arc<RecvData>) -> Option<Arc<RecvData>> {
+        if self.closed.load(Ordering::Acquire) {
+            return Some(data);
+        }
+        let dropped = {
+            let mut items = self.items.lock().unwrap();
+            if items.len() < self.config.capacity {
+                items.push_back(data);
+                None
+            } else {
+                let event = match &*data {
+                    RecvData::Push(push) if droppable(&data) => Some(&push.event),
+                    _ => None,
+                };
+                match (self.config.policy, event) {
+                    (_, None) => {
+                        items.push_back(data);
+                        None
+                    }
+                    (OverflowPolicy::DropNewest, Some(_)) => Some(data),
+                    (OverflowPolicy::DropOldest, Some(event)) => {
+                        let oldest = items.iter().position(|item| match &**item {
+                            RecvData::Push(push) => droppable(item) && &push.event == event,
+                            _ => false,
+                        });
+                        match oldest {
+                            Some(index) => {
+                                let oldest = items.remove(index);
+                                items.push_back(data);
+                                oldest
+                            }
+                            None => Some(data),
+                        }
+                    }
+                }
+            }
+        };
+        if dropped.is_some() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        self.notify.notify_one();
+        dropped
+    }
+
+    /// 取出最早的一条消息, 队列关闭后返回 None
+    pub async fn pop(&self) -> Option<Arc<RecvData>> {
+        loop {
+            if self.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            if let Some(data) = self.items.lock().unwrap().pop_front() {
+                return Some(data);
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.items.lock().unwrap().clear();
+        self.notify.notify_one();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto::message::{Push, Response};
+
+    use super::*;
+
+    fn push(event: &str, seq: u64, ack: bool) -> Arc<RecvData> {
+        let mut push = Push::new();
+        push.event = event.to_string();
+        push.seq = seq;
+        push.ack = ack;
+        Arc::new(RecvData::Push(push))
+    }
+
+    fn response(sequence: &str) -> Arc<RecvData> {
+        let mut response = Response::new();
+        response.sequence = sequence.to_string();
+        Arc::new(RecvData::Response(response))
+    }
+
+    fn queue(capacity: usize, policy: OverflowPolicy) -> InboundQueue {
+        InboundQueue::new(InboundConfig { capacity, policy })
+    }
+
+    fn kinds(queue: &InboundQueue) -> Vec<String> {
+        queue
+            .items
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|item| match &**item {
+                RecvData::Push(push) => push.event.clone(),
+                RecvData::Response(response) => response.sequence.clone(),
+                other => other.kind().to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drop_oldest_only_drops_plain_pushes() {
+        let queue = queue(2, OverflowPolicy::DropOldest);
+        assert!(queue.push(response("r1")).is_none());
+        assert!(queue.push(push("a", 0, false)).is_none());
+        let dropped = queue.push(push("a", 0, false)).unwrap();
+        assert!(matches!(&*dropped, RecvData::Push(push) if push.event == "a"));
+        assert_eq!(kinds(&queue), vec!["r1", "a"]);
+        assert_eq!(queue.dropped(), 1);
+    }
+
+    #[test]
+    fn drop_oldest_only_replaces_same_event() {
+        let queue = queue(3, OverflowPolicy::DropOldest);
+        queue.push(push("a", 0, false));
+        queue.push(push("b", 0, false));
+        queue.push(push("a", 0, false));
+        // 队列中没有 c, 丢弃新到的 c 而不是更早的 a
+        let dropped = queue.push(push("c", 0, false)).unwrap();
+        assert!(matches!(&*dropped, RecvData::Push(push) if push.event == "c"));
+        assert_eq!(kinds(&queue), vec!["a", "b", "a"]);
+        // 替换最早的 b, 其它事件保持原来的顺序
+        let dropped = queue.push(push("b", 0, false)).unwrap();
+        assert!(matches!(&*dropped, RecvData::Push(push) if push.event == "b"));
+        assert_eq!(kinds(&queue), vec!["a", "a", "b"]);
+        // 带序号的同名推送不会被替换
+        queue.push(push("s", 1, false));
+        let dropped = queue.push(push("s", 0, false)).unwrap();
+        assert!(matches!(&*dropped, RecvData::Push(push) if push.seq == 0));
+        assert_eq!(kinds(&queue), vec!["a", "a", "b", "s"]);
+        assert_eq!(queue.dropped(), 3);
+    }
+
+    #[test]
+    fn drop_newest_keeps_queued_messages() {
+        let queue = queue(1, OverflowPolicy::DropNewest);
+        assert!(queue.push(push("a", 0, false)).is_none());
+        assert!(queue.push(push("b", 0, false)).is_some());
+        assert_eq!(kinds(&queue), vec!["a"]);
+    }
+
+    #[test]
+    fn never_drops_responses_or_reliable_pushes() {
+        for policy in [OverflowPolicy::DropOldest, OverflowPolicy::DropNewest] {
+            let queue = queue(1, policy);
+            assert!(queue.push(response("r1")).is_none());
+            assert!(queue.push(response("r2")).is_none());
+            assert!(queue.push(push("seq", 1, false)).is_none());
+            assert!(queue.push(push("ack", 0, true)).is_none());
+            assert_eq!(kinds(&queue), vec!["r1", "r2", "seq", "ack"]);
+            assert_eq!(queue.dropped(), 0);
+        }
+    }
+
+    #[test]
+    fn reliable_message_grows_past_capacity() {
+        let queue = queue(2, OverflowPolicy::DropOldest);
+        queue.push(push("a", 0, false));
+        queue.push(push("b", 0, false));
+        assert!(queue.push(response("r1")).is_none());
+        assert_eq!(kinds(&queue), vec!["a", "b", "r1"]);
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn pop_in_order_until_closed() {
+        let queue = Arc::new(queue(4, OverflowPolicy::DropOldest));
+        queue.push(push("a", 0, false));
+        queue.push(response("r1"));
+        assert!(matches!(&*queue.pop().await.unwrap(), RecvData::Push(_)));
+        assert!(matches!(
+            &*queue.pop().await.unwrap(),
+            RecvData::Response(_)
+        ));
+        let consumer = queue.clone();
+        let handle = tokio::spawn(async move { consumer.pop().await });
+        queue.close();
+        assert!(handle.await.unwrap().is_none());
+        assert!(queue.push(push("b", 0, false)).is_some());
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use super::{
+    traffic::{now_millis, Direction},
+    w_client::RecvData,
+};
+
+const DEFAULT_CAPACITY: usize = 500;
+// 超过这个时间还没有响应的请求不再等待计算延迟
+const PENDING_TIMEOUT: u64 = 60 * 1000;
+// 最多等待响应的请求数, 超过时丢弃最早的一批
+const MAX_PENDING: usize = 1024;
+
+/// 检查器中的一条记录
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct InspectorEntry {
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub id: u64,
+    /// 毫秒时间戳
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub time: u64,
+    pub direction: Direction,
+    pub address: String,
+    pub kind: String,
+    pub size: usize,
+    pub url: Option<String>,
+    pub event: Option<String>,
+    pub sequence: Option<String>,
+    pub status: Option<i32>,
+    /// 响应相对于请求的耗时, 毫秒
+    #[cfg_attr(feature = "ts", ts(type = "number | null"))]
+    pub latency: Option<u64>,
+    pub message: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(optional_fields))]
+#[serde(rename_all = "camelCase", default)]
+pub struct InspectorFilter {
+    pub address: Option<String>,
+    pub kind: Option<String>,
+    pub direction: Option<Direction>,
+    /// 包含匹配
+    pub url: Option<String>,
+    /// 包含匹配
+    pub event: Option<String>,
+    pub sequence: Option<String>,
+    pub status: Option<i32>,
+    #[cfg_attr(feature = "ts", ts(optional, type = "number"))]
+    pub since: Option<u64>,
+    #[cfg_attr(feature = "ts", ts(optional, type = "number"))]
+    pub until: Option<u64>,
+    /// 只返回最新的 n 条
+    pub limit: Option<usize>,
+}
+
+fn contains(value: &Option<String>, pattern: &Option<String>) -> bool {
+    match pattern {
+        Some(pattern) => value
+            .as_ref()
+            .map_or(false, |value| value.contains(pattern.as_str())),
+        None => true,
+    }
+}
+
+impl InspectorFilter {
+    fn matches(&self, entry: &InspectorEntry) -> bool {
+        self.address
+            .as_ref()
+            .map_or(true, |address| &entry.address == address)
+            && self.kind.as_ref().map_or(true, |kind| &entry.kind == kind)
+            && self
+                .direction
+                .map_or(true, |direction| entry.direction == direction)
+            && contains(&entry.url, &self.url)
+            && contains(&entry.event, &self.event)
+            && self
+                .sequence
+                .as_ref()
+                .map_or(true, |sequence| entry.sequence.as_ref() == Some(sequence))
+            && self
+                .status
+                .map_or(true, |status| entry.status == Some(status))
+            && self.since.map_or(true, |since| entry.time >= since)
+            && self.until.map_or(true, |until| entry.time <= until)
+    }
+}
+
+/// 按连接保存最近的帧, 供检查器窗口查询和订阅
+pub struct Inspector {
+    enabled: AtomicBool,
+    capacity: AtomicUsize,
+    next_id: AtomicU64,
+    buffers: DashMap<String, Mutex<VecDeque<InspectorEntry>>>,
+    // sequence -> (发送时间, url)
+    pending: DashMap<String, (u64, String)>,
+    sender: broadcast::Sender<InspectorEntry>,
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self {
+            enabled: AtomicBool::new(true),
+            capacity: AtomicUsize::new(DEFAULT_CAPACITY),
+            next_id: AtomicU64::new(0),
+            buffers: DashMap::new(),
+            pending: DashMap::new(),
+            sender,
+        }
+    }
+}
+
+impl Inspector {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn configure(&self, enabled: Option<bool>, capacity: Option<usize>) {
+        if let Some(enabled) = enabled {
+            self.enabled.store(enabled, Ordering::Relaxed);
+        }
+        if let Some(capacity) = capacity {
+            self.capacity.store(capacity.max(1), Ordering::Relaxed);
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<InspectorEntry> {
+        self.sender.subscribe()
+    }
+
+    pub fn record(
+        &self,
+        direction: Direction,
+        address: &str,
+        size: usize,
+        decoded: &Result<RecvData, String>,
+    ) {
+        let time = now_millis();
+        let mut entry = InspectorEntry {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            time,
+            direction,
+            address: address.to_string(),
+            kind: "invalid".to_string(),
+            size,
+            url: None,
+            event: None,
+            sequence: None,
+            status: None,
+            latency: None,
+            message: Value::Null,
+        };
+        match decoded {
+            Ok(data) => {
+                entry.kind = data.kind().to_string();
+                entry.message = data.json_value();
+                match data {
+                    RecvData::Request(request) => {
+                        entry.url = Some(request.url.clone());
+                        entry.sequence = Some(request.sequence.clone());
+                        if direction == Direction::Send {
+                            self.track(&request.sequence, time, &request.url);
+                        }
+                    }
+                    RecvData::Response(response) => {
+                        entry.sequence = Some(response.sequence.clone());
+                        entry.status = response.status.map(|status| status.value());
+                        if let Some((_, (send_time, url))) = self.pending.remove(&response.sequence)
+                        {
+                            entry.url = Some(url);
+                            entry.latency = Some(time.saturating_sub(send_time));
+                        }
+                    }
+                    RecvData::Push(push) => {
+                        entry.event = Some(push.event.clone());
+                        entry.status = push.status.map(|status| status.value());
+                    }
+                    RecvData::Error(_) | RecvData::Resync(_) => {}
+                }
+            }
+            Err(error) => {
+                entry.message = Value::String(error.clone());
+            }
+        }
+
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        {
+            let buffer = self
+                .buffers
+                .entry(entry.address.clone())
+                .or_insert_with(|| Mutex::new(VecDeque::new()));
+            let mut buffer = buffer.lock().unwrap();
+            while buffer.len() >= capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back(entry.clone());
+        }
+        // 没有订阅者时发送失败是正常的
+        let _ = self.sender.send(entry);
+    }
+
+    fn track(&self, sequence: &str, time: u64, url: &str) {
+        if self.pending.len() >= MAX_PENDING {
+            self.pending
+                .retain(|_, (send_time, _)| time.saturating_sub(*send_time) < PENDING_TIMEOUT);
+        }
+        if self.pending.len() >= MAX_PENDING {
+            // 都没有超时, 丢弃最早的四分之一, 它们的响应不再计算延迟
+            let mut pending: Vec<(u64, String)> = self
+                .pending
+                .iter()
+                .map(|item| (item.value().0, item.key().clone()))
+                .collect();
+            pending.sort();
+            let evict = pending.len() - MAX_PENDING * 3 / 4;
+            for (_, sequence) in pending.into_iter().take(evict) {
+                self.pending.remove(&sequence);
+            }
+        }
+        self.pending
+            .insert(sequence.to_string(), (time, url.to_string()));
+    }
+
+    pub fn query(&self, filter: &InspectorFilter) -> Vec<InspectorEntry> {
+        let mut entries: Vec<InspectorEntry> = self
+            .buffers
+            .iter()
+            .filter(|buffer| {
+                filter
+                    .address
+                    .as_ref()
+                    .map_or(true, |address| buffer.key() == address)
+            })
+            .flat_map(|buffer| {
+                buffer
+                    .value()
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|entry| filter.matches(entry))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.id);
+        if let Some(limit) = filter.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+        entries
+    }
+
+    pub fn clear(&self, address: Option<&str>) {
+        match address {
+            Some(address) => {
+                self.buffers.remove(address);
+            }
+            None => self.buffers.clear(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proto::message::{Push, Request, Response};
+
+    use super::*;
+
+    fn request(sequence: &str, url: &str) -> Result<RecvData, String> {
+        let mut request = Request::new();
+        request.sequence = sequence.to_string();
+        request.url = url.to_string();
+        Ok(RecvData::Request(request))
+    }
+
+    fn response(sequence: &str) -> Result<RecvData, String> {
+        let mut response = Response::new();
+        response.sequence = sequence.to_string();
+        Ok(RecvData::Response(response))
+    }
+
+    fn push(event: &str) -> Result<RecvData, String> {
+        let mut push = Push::new();
+        push.event = event.to_string();
+        Ok(RecvData::Push(push))
+    }
+
+    fn events(entries: &[InspectorEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| entry.event.clone().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn filter_matches() {
+        let inspector = Inspector::default();
+        inspector.record(Direction::Send, "a", 1, &request("s1", "/task/run"));
+        inspector.record(Direction::Recv, "a", 1, &push("task/done"));
+        inspector.record(Direction::Recv, "b", 1, &Err("bad".to_string()));
+        let entry = inspector.query(&InspectorFilter::default()).remove(0);
+
+        let cases = [
+            (InspectorFilter::default(), true),
+            (
+                InspectorFilter {
+                    address: Some("a".to_string()),
+                    kind: Some(entry.kind.clone()),
+                    direction: Some(Direction::Send),
+                    url: Some("task".to_string()),
+                    sequence: Some("s1".to_string()),
+                    since: Some(entry.time),
+                    until: Some(entry.time),
+                    ..Default::default()
+                },
+                true,
+            ),
+            (
+                InspectorFilter {
+                    address: Some("b".to_string()),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                InspectorFilter {
+                    direction: Some(Direction::Recv),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                InspectorFilter {
+                    url: Some("/push".to_string()),
+                    ..Default::default()
+                },
+                false,
+            ),
+            // 没有 event 的记录不匹配 event 条件
+            (
+                InspectorFilter {
+                    event: Some("".to_string()),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                InspectorFilter {
+                    sequence: Some("s".to_string()),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                InspectorFilter {
+                    status: Some(200),
+                    ..Default::default()
+                },
+                false,
+            ),
+            (
+                InspectorFilter {
+                    since: Some(entry.time + 1),
+                    ..Default::default()
+                },
+                false,
+            ),
+        ];
+        for (filter, expected) in cases.iter() {
+            assert_eq!(filter.matches(&entry), *expected, "{:?}", filter);
+        }
+
+        let filter = InspectorFilter {
+            event: Some("done".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(events(&inspector.query(&filter)), vec!["task/done"]);
+        let filter = InspectorFilter {
+            kind: Some("invalid".to_string()),
+            ..Default::default()
+        };
+        let invalid = inspector.query(&filter);
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].address, "b");
+    }
+
+    #[test]
+    fn capacity_evicts_oldest_per_address() {
+        let inspector = Inspector::default();
+        inspector.configure(None, Some(3));
+        for index in 0..5 {
+            inspector.record(Direction::Recv, "a", 1, &push(&format!("a{}", index)));
+        }
+        inspector.record(Direction::Recv, "b", 1, &push("b0"));
+        let filter = InspectorFilter {
+            address: Some("a".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(events(&inspector.query(&filter)), vec!["a2", "a3", "a4"]);
+        assert_eq!(
+            events(&inspector.query(&InspectorFilter::default())),
+            vec!["a2", "a3", "a4", "b0"]
+        );
+    }
+
+    #[test]
+    fn limit_returns_newest() {
+        let inspector = Inspector::default();
+        inspector.record(Direction::Recv, "a", 1, &push("1"));
+        inspector.record(Direction::Recv, "b", 1, &push("2"));
+        inspector.record(Direction::Recv, "a", 1, &push("3"));
+        let limit = |limit| InspectorFilter {
+            limit: Some(limit),
+            ..Default::default()
+        };
+        assert_eq!(events(&inspector.query(&limit(2))), vec!["2", "3"]);
+        assert_eq!(events(&inspector.query(&limit(10))), vec!["1", "2", "3"]);
+        assert!(inspector.query(&limit(0)).is_empty());
+    }
+
+    #[test]
+    fn latency_pairs_response_with_request() {
+        let inspector = Inspector::default();
+        inspector.record(Direction::Send, "a", 1, &request("s1", "/x"));
+        // 收到的请求不等待响应
+        inspector.record(Direction::Recv, "a", 1, &request("s2", "/y"));
+        inspector.record(Direction::Recv, "a", 1, &response("s1"));
+        inspector.record(Direction::Recv, "a", 1, &response("s1"));
+        inspector.record(Direction::Recv, "a", 1, &response("s2"));
+        let responses = inspector.query(&InspectorFilter {
+            kind: Some("response".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0].url.as_deref(), Some("/x"));
+        assert!(responses[0].latency.is_some());
+        // 每个请求只配对一次
+        assert_eq!(responses[1].latency, None);
+        assert_eq!(responses[2].url, None);
+        assert_eq!(responses[2].latency, None);
+        assert!(inspector.pending.is_empty());
+    }
+
+    #[test]
+    fn pending_is_bounded() {
+        let inspector = Inspector::default();
+        inspector.track("stale", 0, "/x");
+        let now = PENDING_TIMEOUT + 1;
+        for index in 0..MAX_PENDING * 2 {
+            inspector.track(&format!("s{}", index), now + index as u64, "/x");
+            assert!(inspector.pending.len() <= MAX_PENDING);
+        }
+        // 超时的先被清理, 然后丢弃最早的
+        assert!(!inspector.pending.contains_key("stale"));
+        assert!(!inspector.pending.contains_key("s0"));
+        let last = format!("s{}", MAX_PENDING * 2 - 1);
+        assert!(inspector.pending.contains_key(&last));
+    }
+}
@@ -0,0 +1,22 @@
+//! 任务服务的协议客户端, 不依赖 tauri.
+//! 事件通过 [`sink::EventSink`] 发出, 桌面端由 connect 插件实现为发往窗口
+pub mod ack;
+pub mod c_macro;
+pub mod client_manage;
+pub mod clock;
+pub mod emitter;
+pub mod error;
+pub mod inbound;
+pub mod inspector;
+pub mod logger;
+pub mod metrics;
+pub mod push_policy;
+pub mod recorder;
+pub mod replay;
+pub mod sequence;
+pub mod sink;
+pub mod store;
+pub mod traffic;
+pub mod typed;
+pub mod utils;
+pub mod w_client;
@@ -0,0 +1,139 @@
+use std::fmt::{self, Display};
+
+use serde::Serialize;
+
+/// 日志的上下文, 作为 json 记录的字段输出, 方便按连接/请求检索
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogContext {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(rename = "client", skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl LogContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(mut self, address: &str) -> Self {
+        self.address = Some(address.to_string());
+        self
+    }
+
+    pub fn client_id(mut self, client_id: &str) -> Self {
+        self.client_id = Some(client_id.to_string());
+        self
+    }
+
+    pub fn window(mut self, window: &str) -> Self {
+        self.window = Some(window.to_string());
+        self
+    }
+
+    pub fn sequence(mut self, sequence: &str) -> Self {
+        self.sequence = Some(sequence.to_string());
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+}
+
+/// 一条带上下文的日志, 输出为一行 json: `{"message":"...","address":"...","url":"..."}`
+#[derive(Debug, Serialize)]
+pub struct LogRecord<'a> {
+    pub message: String,
+    #[serde(flatten)]
+    pub context: &'a LogContext,
+}
+
+impl<'a> LogRecord<'a> {
+    pub fn new(message: String, context: &'a LogContext) -> Self {
+        Self { message, context }
+    }
+}
+
+impl Display for LogRecord<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let record = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&record)
+    }
+}
+
+/// 带上下文的日志: `ctx_log!(log::Level::Error, ctx, "request error: {}", err)`.
+/// 级别没有开启时不会格式化, 也不会求值 `ctx`
+#[macro_export]
+macro_rules! ctx_log {
+    ($level:expr, $ctx:expr, $($arg:tt)+) => {{
+        let level = $level;
+        if log::log_enabled!(level) {
+            log::log!(
+                level,
+                "{}",
+                $crate::logger::LogRecord::new(format!($($arg)+), &$ctx)
+            )
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! ctx_error {
+    ($ctx:expr, $($arg:tt)+) => { $crate::ctx_log!(log::Level::Error, $ctx, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! ctx_warn {
+    ($ctx:expr, $($arg:tt)+) => { $crate::ctx_log!(log::Level::Warn, $ctx, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! ctx_info {
+    ($ctx:expr, $($arg:tt)+) => { $crate::ctx_log!(log::Level::Info, $ctx, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! ctx_debug {
+    ($ctx:expr, $($arg:tt)+) => { $crate::ctx_log!(log::Level::Debug, $ctx, $($arg)+) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_fields() {
+        let context = LogContext::new()
+            .address("127.0.0.1:9673")
+            .client_id("c1")
+            .url("/task/list");
+        let record = LogRecord::new("request error: \"timeout\"".to_string(), &context);
+        let value: serde_json::Value = serde_json::from_str(&record.to_string()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "message": "request error: \"timeout\"",
+                "address": "127.0.0.1:9673",
+                "client": "c1",
+                "url": "/task/list"
+            })
+        );
+    }
+
+    #[test]
+    fn empty_context() {
+        let context = LogContext::new();
+        assert_eq!(
+            LogRecord::new("closed".to_string(), &context).to_string(),
+            r#"{"message":"closed"}"#
+        );
+    }
+}
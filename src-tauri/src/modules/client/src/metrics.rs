@@ -0,0 +1,562 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    sync::Mutex,
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+use super::{
+    clock::{ClockEstimator, ClockSample, ClockSnapshot},
+    traffic::Direction,
+    w_client::RecvData,
+};
+
+/// 延迟直方图的桶上限, 毫秒
+const LATENCY_BUCKETS: [f64; 12] = [
+    5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0,
+];
+
+/// 一次请求的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    /// 服务端返回了非 OK 的状态
+    Status(i32),
+    Timeout,
+    SendError,
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    count: u64,
+    sum: f64,
+    max: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; LATENCY_BUCKETS.len() + 1],
+            count: 0,
+            sum: 0.0,
+            max: 0.0,
+        }
+    }
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        let index = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+    }
+
+    /// 在桶内线性插值估算分位数
+    fn percentile(&self, p: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let rank = p * self.count as f64;
+        let mut seen = 0u64;
+        for (index, count) in self.buckets.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            if (seen + count) as f64 >= rank {
+                let lower = if index == 0 {
+                    0.0
+                } else {
+                    LATENCY_BUCKETS[index - 1]
+                };
+                let upper = LATENCY_BUCKETS.get(index).copied().unwrap_or(self.max);
+                let fraction = (rank - seen as f64) / *count as f64;
+                return (lower + (upper - lower) * fraction).min(self.max);
+            }
+            seen += count;
+        }
+        self.max
+    }
+
+    fn snapshot(&self) -> LatencySnapshot {
+        LatencySnapshot {
+            count: self.count,
+            mean: if self.count == 0 {
+                0.0
+            } else {
+                self.sum / self.count as f64
+            },
+            p50: self.percentile(0.5),
+            p90: self.percentile(0.9),
+            p99: self.percentile(0.99),
+            max: self.max,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct UrlMetrics {
+    requests: u64,
+    ok: u64,
+    timeouts: u64,
+    send_errors: u64,
+    statuses: BTreeMap<i32, u64>,
+    latency: Histogram,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ConnectionMetrics {
+    bytes_in: u64,
+    bytes_out: u64,
+    frames_in: u64,
+    frames_out: u64,
+    invalid_frames: u64,
+    pushes: BTreeMap<String, u64>,
+    urls: BTreeMap<String, UrlMetrics>,
+    clock: ClockEstimator,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct LatencySnapshot {
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub count: u64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct UrlSnapshot {
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub requests: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub ok: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub timeouts: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub send_errors: u64,
+    /// 非 OK 状态码 -> 次数
+    #[cfg_attr(feature = "ts", ts(type = "{ [key in number]?: number }"))]
+    pub statuses: BTreeMap<i32, u64>,
+    /// 毫秒
+    pub latency: LatencySnapshot,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSnapshot {
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub bytes_in: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub bytes_out: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub frames_in: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub frames_out: u64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub invalid_frames: u64,
+    #[cfg_attr(feature = "ts", ts(type = "{ [key in string]?: number }"))]
+    pub pushes: BTreeMap<String, u64>,
+    pub urls: BTreeMap<String, UrlSnapshot>,
+    /// 服务端的时钟偏差和往返时间, 服务端没有返回毫秒时间时为空
+    pub clock: Option<ClockSnapshot>,
+}
+
+/// 客户端视角的请求和流量统计, 按连接地址和 url 分组
+#[derive(Default)]
+pub struct Metrics {
+    conns: DashMap<String, Mutex<ConnectionMetrics>>,
+}
+
+impl Metrics {
+    fn with<F: FnOnce(&mut ConnectionMetrics)>(&self, address: &str, f: F) {
+        let conn = self
+            .conns
+            .entry(address.to_string())
+            .or_insert_with(|| Mutex::new(ConnectionMetrics::default()));
+        let mut conn = conn.lock().unwrap();
+        f(&mut conn);
+    }
+
+    pub fn observe_frame(
+        &self,
+        direction: Direction,
+        address: &str,
+        size: usize,
+        decoded: &Result<RecvData, String>,
+    ) {
+        self.with(address, |conn| {
+            match direction {
+                Direction::Send => {
+                    conn.bytes_out += size as u64;
+                    conn.frames_out += 1;
+                }
+                Direction::Recv => {
+                    conn.bytes_in += size as u64;
+                    conn.frames_in += 1;
+                }
+            }
+            match decoded {
+                Ok(RecvData::Push(push)) => {
+                    *conn.pushes.entry(push.event.clone()).or_insert(0) += 1;
+                }
+                Err(_) => conn.invalid_frames += 1,
+                _ => {}
+            }
+        });
+    }
+
+    pub fn observe_request(&self, address: &str, url: &str, elapsed: Duration, outcome: Outcome) {
+        self.with(address, |conn| {
+            let url = conn.urls.entry(url.to_string()).or_default();
+            url.requests += 1;
+            match outcome {
+                Outcome::Ok => url.ok += 1,
+                Outcome::Status(status) => *url.statuses.entry(status).or_insert(0) += 1,
+                Outcome::Timeout => url.timeouts += 1,
+                Outcome::SendError => url.send_errors += 1,
+            }
+            // 超时和发送失败的耗时没有意义, 不计入延迟
+            if matches!(outcome, Outcome::Ok | Outcome::Status(_)) {
+                url.latency.observe(elapsed.as_secs_f64() * 1000.0);
+            }
+        });
+    }
+
+    pub fn observe_clock(&self, address: &str, sample: ClockSample) {
+        self.with(address, |conn| conn.clock.observe(sample));
+    }
+
+    pub fn clock(&self, address: &str) -> Option<ClockSnapshot> {
+        self.conns.get(address)?.lock().unwrap().clock.snapshot()
+    }
+
+    pub fn reset(&self) {
+        self.conns.clear();
+    }
+
+    /// 复制当前的统计, `reset` 为 true 时同时清空. 每个连接在自己的锁内复制和清空,
+    /// 期间的观测要么在副本中, 要么留到下一次, 不会丢失
+    pub fn collect(&self, reset: bool) -> Metrics {
+        let conns = DashMap::new();
+        for conn in self.conns.iter() {
+            let mut metrics = conn.value().lock().unwrap();
+            let copy = if reset {
+                std::mem::take(&mut *metrics)
+            } else {
+                metrics.clone()
+            };
+            conns.insert(conn.key().clone(), Mutex::new(copy));
+        }
+        Metrics { conns }
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, ConnectionSnapshot> {
+        self.conns
+            .iter()
+            .map(|conn| {
+                let metrics = conn.value().lock().unwrap();
+                let snapshot = ConnectionSnapshot {
+                    bytes_in: metrics.bytes_in,
+                    bytes_out: metrics.bytes_out,
+                    frames_in: metrics.frames_in,
+                    frames_out: metrics.frames_out,
+                    invalid_frames: metrics.invalid_frames,
+                    pushes: metrics.pushes.clone(),
+                    urls: metrics
+                        .urls
+                        .iter()
+                        .map(|(url, metrics)| {
+                            (
+                                url.clone(),
+                                UrlSnapshot {
+                                    requests: metrics.requests,
+                                    ok: metrics.ok,
+                                    timeouts: metrics.timeouts,
+                                    send_errors: metrics.send_errors,
+                                    statuses: metrics.statuses.clone(),
+                                    latency: metrics.latency.snapshot(),
+                                },
+                            )
+                        })
+                        .collect(),
+                    clock: metrics.clock.snapshot(),
+                };
+                (conn.key().clone(), snapshot)
+            })
+            .collect()
+    }
+
+    /// Prometheus 文本格式
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut conns: Vec<(String, ConnectionMetrics)> = self
+            .conns
+            .iter()
+            .map(|conn| (conn.key().clone(), conn.value().lock().unwrap().clone()))
+            .collect();
+        conns.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let _ = writeln!(out, "# TYPE task_client_bytes_total counter");
+        for (address, conn) in conns.iter() {
+            let address = escape(address);
+            let _ = writeln!(
+                out,
+                "task_client_bytes_total{{address=\"{}\",direction=\"in\"}} {}",
+                address, conn.bytes_in
+            );
+            let _ = writeln!(
+                out,
+                "task_client_bytes_total{{address=\"{}\",direction=\"out\"}} {}",
+                address, conn.bytes_out
+            );
+        }
+        let _ = writeln!(out, "# TYPE task_client_frames_total counter");
+        for (address, conn) in conns.iter() {
+            let address = escape(address);
+            let _ = writeln!(
+                out,
+                "task_client_frames_total{{address=\"{}\",direction=\"in\"}} {}",
+                address, conn.frames_in
+            );
+            let _ = writeln!(
+                out,
+                "task_client_frames_total{{address=\"{}\",direction=\"out\"}} {}",
+                address, conn.frames_out
+            );
+            let _ = writeln!(
+                out,
+                "task_client_frames_total{{address=\"{}\",direction=\"invalid\"}} {}",
+                address, conn.invalid_frames
+            );
+        }
+        let _ = writeln!(out, "# TYPE task_client_pushes_total counter");
+        for (address, conn) in conns.iter() {
+            for (event, count) in conn.pushes.iter() {
+                let _ = writeln!(
+                    out,
+                    "task_client_pushes_total{{address=\"{}\",event=\"{}\"}} {}",
+                    escape(address),
+                    escape(event),
+                    count
+                );
+            }
+        }
+        let _ = writeln!(out, "# TYPE task_client_requests_total counter");
+        for (address, conn) in conns.iter() {
+            for (url, metrics) in conn.urls.iter() {
+                let labels = format!("address=\"{}\",url=\"{}\"", escape(address), escape(url));
+                let _ = writeln!(
+                    out,
+                    "task_client_requests_total{{{},outcome=\"ok\"}} {}",
+                    labels, metrics.ok
+                );
+                let _ = writeln!(
+                    out,
+                    "task_client_requests_total{{{},outcome=\"timeout\"}} {}",
+                    labels, metrics.timeouts
+                );
+                let _ = writeln!(
+                    out,
+                    "task_client_requests_total{{{},outcome=\"send_error\"}} {}",
+                    labels, metrics.send_errors
+                );
+                for (status, count) in metrics.statuses.iter() {
+                    let _ = writeln!(
+                        out,
+                        "task_client_requests_total{{{},outcome=\"status\",status=\"{}\"}} {}",
+                        labels, status, count
+                    );
+                }
+            }
+        }
+        let _ = writeln!(out, "# TYPE task_client_request_duration_ms histogram");
+        for (address, conn) in conns.iter() {
+            for (url, metrics) in conn.urls.iter() {
+                let labels = format!("address=\"{}\",url=\"{}\"", escape(address), escape(url));
+                let mut cumulative = 0;
+                for (index, bound) in LATENCY_BUCKETS.iter().enumerate() {
+                    cumulative += metrics.latency.buckets[index];
+                    let _ = writeln!(
+                        out,
+                        "task_client_request_duration_ms_bucket{{{},le=\"{}\"}} {}",
+                        labels, bound, cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "task_client_request_duration_ms_bucket{{{},le=\"+Inf\"}} {}",
+                    labels, metrics.latency.count
+                );
+                let _ = writeln!(
+                    out,
+                    "task_client_request_duration_ms_sum{{{}}} {}",
+                    labels, metrics.latency.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "task_client_request_duration_ms_count{{{}}} {}",
+                    labels, metrics.latency.count
+                );
+            }
+        }
+        let _ = writeln!(out, "# TYPE task_client_clock_offset_ms gauge");
+        for (address, conn) in conns.iter() {
+            if let Some(clock) = conn.clock.snapshot() {
+                let _ = writeln!(
+                    out,
+                    "task_client_clock_offset_ms{{address=\"{}\"}} {}",
+                    escape(address),
+                    clock.offset_ms
+                );
+            }
+        }
+        let _ = writeln!(out, "# TYPE task_client_rtt_ms gauge");
+        for (address, conn) in conns.iter() {
+            if let Some(clock) = conn.clock.snapshot() {
+                let _ = writeln!(
+                    out,
+                    "task_client_rtt_ms{{address=\"{}\"}} {}",
+                    escape(address),
+                    clock.rtt_ms
+                );
+            }
+        }
+        out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(values: &[f64]) -> Histogram {
+        let mut histogram = Histogram::default();
+        for value in values {
+            histogram.observe(*value);
+        }
+        histogram
+    }
+
+    #[test]
+    fn percentile_interpolates_in_bucket() {
+        assert_eq!(Histogram::default().percentile(0.5), 0.0);
+        assert_eq!(Histogram::default().snapshot().mean, 0.0);
+
+        // 都在 (10, 25] 中
+        let latency = histogram(&[12.0, 14.0, 16.0, 20.0]);
+        assert_eq!(latency.percentile(0.5), 17.5);
+        assert_eq!(latency.percentile(0.25), 13.75);
+        // 不超过观测到的最大值
+        assert_eq!(latency.percentile(1.0), 20.0);
+
+        let latency = histogram(&[3.0, 7.0]);
+        assert_eq!(latency.percentile(0.5), 5.0);
+        assert_eq!(latency.percentile(1.0), 7.0);
+    }
+
+    #[test]
+    fn percentile_in_overflow_bucket() {
+        // 超过最大的桶时以最大值作为上限
+        let latency = histogram(&[40000.0, 50000.0]);
+        assert_eq!(latency.buckets[LATENCY_BUCKETS.len()], 2);
+        assert_eq!(latency.percentile(0.5), 40000.0);
+        assert_eq!(latency.percentile(0.99), 49800.0);
+        assert_eq!(latency.snapshot().max, 50000.0);
+    }
+
+    #[test]
+    fn prometheus_histogram() {
+        let metrics = Metrics::default();
+        let address = "host\"a\\b\nc";
+        for ms in [3, 20] {
+            metrics.observe_request(address, "/x", Duration::from_millis(ms), Outcome::Ok);
+        }
+        metrics.observe_request(address, "/x", Duration::ZERO, Outcome::Timeout);
+        metrics.observe_request(
+            address,
+            "/x",
+            Duration::from_millis(40000),
+            Outcome::Status(500),
+        );
+        let text = metrics.to_prometheus();
+        let labels = r#"address="host\"a\\b\nc",url="/x""#;
+        let expected = [
+            r#"task_client_bytes_total{address="host\"a\\b\nc",direction="in"} 0"#.to_string(),
+            format!(r#"task_client_requests_total{{{},outcome="ok"}} 2"#, labels),
+            format!(
+                r#"task_client_requests_total{{{},outcome="timeout"}} 1"#,
+                labels
+            ),
+            format!(
+                r#"task_client_requests_total{{{},outcome="status",status="500"}} 1"#,
+                labels
+            ),
+            // 桶是累计的, 超时不计入延迟
+            format!(
+                r#"task_client_request_duration_ms_bucket{{{},le="5"}} 1"#,
+                labels
+            ),
+            format!(
+                r#"task_client_request_duration_ms_bucket{{{},le="10"}} 1"#,
+                labels
+            ),
+            format!(
+                r#"task_client_request_duration_ms_bucket{{{},le="25"}} 2"#,
+                labels
+            ),
+            format!(
+                r#"task_client_request_duration_ms_bucket{{{},le="30000"}} 2"#,
+                labels
+            ),
+            format!(
+                r#"task_client_request_duration_ms_bucket{{{},le="+Inf"}} 3"#,
+                labels
+            ),
+            format!(r#"task_client_request_duration_ms_sum{{{}}} 40023"#, labels),
+            format!(r#"task_client_request_duration_ms_count{{{}}} 3"#, labels),
+        ];
+        for line in expected.iter() {
+            assert!(text.lines().any(|text| text == line), "{}\n{}", line, text);
+        }
+        // 换行被转义, 不会把一行拆开
+        assert!(!text.lines().any(|line| line.starts_with("c\"")));
+    }
+
+    #[test]
+    fn collect_and_reset() {
+        let metrics = Metrics::default();
+        metrics.observe_request("a", "/x", Duration::from_millis(1), Outcome::Ok);
+        let copy = metrics.collect(false);
+        assert_eq!(copy.snapshot()["a"].urls["/x"].requests, 1);
+
+        let taken = metrics.collect(true);
+        assert_eq!(taken.snapshot()["a"].urls["/x"].requests, 1);
+        assert!(metrics.snapshot()["a"].urls.is_empty());
+        // 清空之后的观测留在原来的统计中
+        metrics.observe_request("a", "/x", Duration::from_millis(1), Outcome::Ok);
+        assert_eq!(metrics.snapshot()["a"].urls["/x"].requests, 1);
+        assert_eq!(taken.snapshot()["a"].urls["/x"].requests, 1);
+    }
+}
@@ -0,0 +1,489 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use proto::message::Push;
+use serde::{Deserialize, Serialize};
+use tokio::{task::JoinHandle, time::Instant};
+
+/// 匹配所有没有单独配置的事件
+pub const ANY_EVENT: &str = "*";
+/// 防抖没有配置 `max_wait_ms` 时, 最长等待时间为 `wait_ms` 的倍数
+pub const DEBOUNCE_MAX_WAIT_FACTOR: u64 = 10;
+
+/// 单个 push 事件发往页面的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PushPolicy {
+    /// 每条都立即发送
+    #[default]
+    All,
+    /// 空闲时立即发送, 之后每个间隔内最多发送一次, 只保留间隔内最新的一条
+    #[serde(rename_all = "camelCase")]
+    Latest {
+        #[cfg_attr(feature = "ts", ts(type = "number"))]
+        interval_ms: u64,
+    },
+    /// 停止推送 `wait_ms` 之后才发送最后一条. 持续推送时最多等待 `max_wait_ms`
+    /// (默认为 `wait_ms` 的 [`DEBOUNCE_MAX_WAIT_FACTOR`] 倍) 就发送一次
+    #[serde(rename_all = "camelCase")]
+    Debounce {
+        #[cfg_attr(feature = "ts", ts(type = "number"))]
+        wait_ms: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "ts", ts(optional, type = "number"))]
+        max_wait_ms: Option<u64>,
+    },
+    /// 按固定间隔采样, 间隔内有新消息时发送最新的一条
+    #[serde(rename_all = "camelCase")]
+    Sample {
+        #[cfg_attr(feature = "ts", ts(type = "number"))]
+        interval_ms: u64,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct PushStats {
+    pub policy: PushPolicy,
+    /// 服务端推送的条数
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub received: u64,
+    /// 实际发往页面的条数
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub emitted: u64,
+    /// 被后来的消息覆盖而没有发送的条数
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub coalesced: u64,
+}
+
+type Emit = Arc<dyn Fn(&Push) + Send + Sync>;
+
+#[derive(Default)]
+struct Counters {
+    received: AtomicU64,
+    emitted: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+#[derive(Default)]
+struct SlotState {
+    pending: Option<Push>,
+    last_emit: Option<Instant>,
+    timer: Option<JoinHandle<()>>,
+    // 防抖时这一批消息中第一条到达的时间
+    first_pending: Option<Instant>,
+    // 防抖计时器的代数, 过期的计时器不再发送
+    generation: u64,
+}
+
+/// 单个事件的发送状态
+struct Slot {
+    policy: PushPolicy,
+    // 是否为该事件单独配置的策略, 否则来自 ANY_EVENT
+    explicit: bool,
+    counters: Arc<Counters>,
+    state: Mutex<SlotState>,
+}
+
+impl Slot {
+    fn new(policy: PushPolicy, explicit: bool, counters: Arc<Counters>) -> Self {
+        Self {
+            policy,
+            explicit,
+            counters,
+            state: Mutex::new(SlotState::default()),
+        }
+    }
+
+    fn emit(&self, emit: &Emit, push: &Push) {
+        self.counters.emitted.fetch_add(1, Ordering::Relaxed);
+        emit(push);
+    }
+
+    /// 用新消息替换等待中的消息, 被替换的计入 coalesced
+    fn replace_pending(&self, state: &mut SlotState, push: Push) {
+        if state.pending.replace(push).is_some() {
+            self.counters.coalesced.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn take_pending(&self) -> Option<Push> {
+        let mut state = self.state.lock().unwrap();
+        let pending = state.pending.take();
+        if pending.is_some() {
+            state.last_emit = Some(Instant::now());
+        } else {
+            state.timer = None;
+        }
+        pending
+    }
+
+    fn push(self: &Arc<Self>, emit: &Emit, push: Push) {
+        self.counters.received.fetch_add(1, Ordering::Relaxed);
+        match self.policy {
+            PushPolicy::All => self.emit(emit, &push),
+            PushPolicy::Latest { interval_ms } => {
+                let interval = Duration::from_millis(interval_ms);
+                let mut state = self.state.lock().unwrap();
+                let idle = state.timer.is_none()
+                    && state
+                        .last_emit
+                        .map_or(true, |last| last.elapsed() >= interval);
+                if idle {
+                    state.last_emit = Some(Instant::now());
+                    drop(state);
+                    self.emit(emit, &push);
+                    return;
+                }
+                self.replace_pending(&mut state, push);
+                if state.timer.is_none() {
+                    let mut wait = state.last_emit.map_or(Duration::ZERO, |last| {
+                        interval.saturating_sub(last.elapsed())
+                    });
+                    let (slot, emit) = (self.clone(), emit.clone());
+                    state.timer = Some(tokio::spawn(async move {
+                        // 间隔结束时发送最新的一条, 再等一个间隔仍没有新消息才回到空闲
+                        loop {
+                            tokio::time::sleep(wait).await;
+                            match slot.take_pending() {
+                                Some(push) => slot.emit(&emit, &push),
+                                None => break,
+                            }
+                            wait = interval;
+                        }
+                    }));
+                }
+            }
+            PushPolicy::Debounce {
+                wait_ms,
+                max_wait_ms,
+            } => {
+                let max_wait = Duration::from_millis(
+                    max_wait_ms.unwrap_or_else(|| wait_ms.saturating_mul(DEBOUNCE_MAX_WAIT_FACTOR)),
+                );
+                let mut state = self.state.lock().unwrap();
+                self.replace_pending(&mut state, push);
+                if let Some(timer) = state.timer.take() {
+                    timer.abort();
+                }
+                let first = *state.first_pending.get_or_insert_with(Instant::now);
+                // 不能超过这一批第一条到达后的最长等待时间
+                let wait =
+                    Duration::from_millis(wait_ms).min(max_wait.saturating_sub(first.elapsed()));
+                state.generation += 1;
+                let generation = state.generation;
+                let (slot, emit) = (self.clone(), emit.clone());
+                state.timer = Some(tokio::spawn(async move {
+                    tokio::time::sleep(wait).await;
+                    let pending = {
+                        let mut state = slot.state.lock().unwrap();
+                        // 已经有更新的消息重新计时
+                        if state.generation != generation {
+                            return;
+                        }
+                        state.timer = None;
+                        state.first_pending = None;
+                        state.pending.take()
+                    };
+                    if let Some(push) = pending {
+                        slot.emit(&emit, &push);
+                    }
+                }));
+            }
+            PushPolicy::Sample { interval_ms } => {
+                let mut state = self.state.lock().unwrap();
+                self.replace_pending(&mut state, push);
+                if state.timer.is_none() {
+                    let (slot, emit) = (self.clone(), emit.clone());
+                    state.timer = Some(tokio::spawn(async move {
+                        let period = Duration::from_millis(interval_ms.max(1));
+                        let mut ticker = tokio::time::interval(period);
+                        ticker.tick().await;
+                        loop {
+                            ticker.tick().await;
+                            // 一个间隔内没有新消息就停止采样, 下一条消息到来时再启动
+                            match slot.take_pending() {
+                                Some(push) => slot.emit(&emit, &push),
+                                None => break,
+                            }
+                        }
+                    }));
+                }
+            }
+        }
+    }
+
+    /// 停止计时器, 等待中的消息立即发送
+    fn flush(&self, emit: &Emit) {
+        let pending = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(timer) = state.timer.take() {
+                timer.abort();
+            }
+            state.first_pending = None;
+            state.pending.take()
+        };
+        if let Some(push) = pending {
+            self.emit(emit, &push);
+        }
+    }
+
+    fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(timer) = state.timer.take() {
+            timer.abort();
+        }
+        state.first_pending = None;
+        state.pending = None;
+    }
+}
+
+/// 按事件名对 push 做合并和节流, 之后再发往页面
+pub struct PushGate {
+    emit: Emit,
+    policies: DashMap<String, PushPolicy>,
+    slots: DashMap<String, Arc<Slot>>,
+    counters: DashMap<String, Arc<Counters>>,
+}
+
+impl PushGate {
+    pub fn new(emit: impl Fn(&Push) + Send + Sync + 'static) -> Self {
+        Self {
+            emit: Arc::new(emit),
+            policies: DashMap::new(),
+            slots: DashMap::new(),
+            counters: DashMap::new(),
+        }
+    }
+
+    fn counters(&self, event: &str) -> Arc<Counters> {
+        self.counters
+            .entry(event.to_string())
+            .or_default()
+            .value()
+            .clone()
+    }
+
+    fn resolve(&self, event: &str) -> (PushPolicy, bool) {
+        if let Some(policy) = self.policies.get(event) {
+            return (*policy, true);
+        }
+        let policy = self
+            .policies
+            .get(ANY_EVENT)
+            .map(|policy| *policy)
+            .unwrap_or_default();
+        (policy, false)
+    }
+
+    pub fn push(&self, push: Push) {
+        let slot = self
+            .slots
+            .entry(push.event.clone())
+            .or_insert_with(|| {
+                let (policy, explicit) = self.resolve(&push.event);
+                Arc::new(Slot::new(policy, explicit, self.counters(&push.event)))
+            })
+            .value()
+            .clone();
+        slot.push(&self.emit, push);
+    }
+
+    /// 修改事件的策略, `event` 为 ANY_EVENT 时作用于所有没有单独配置的事件.
+    /// 旧策略下等待中的消息会立即发送.
+    pub fn set_policy(&self, event: &str, policy: Option<PushPolicy>) {
+        match policy {
+            Some(policy) => {
+                self.policies.insert(event.to_string(), policy);
+            }
+            None => {
+                self.policies.remove(event);
+            }
+        }
+        let stale: Vec<Arc<Slot>> = if event == ANY_EVENT {
+            let keys: Vec<String> = self
+                .slots
+                .iter()
+                .filter(|slot| !slot.value().explicit)
+                .map(|slot| slot.key().clone())
+                .collect();
+            keys.iter()
+                .filter_map(|key| self.slots.remove(key).map(|(_, slot)| slot))
+                .collect()
+        } else {
+            self.slots
+                .remove(event)
+                .map(|(_, slot)| slot)
+                .into_iter()
+                .collect()
+        };
+        for slot in stale {
+            slot.flush(&self.emit);
+        }
+    }
+
+    pub fn policies(&self) -> HashMap<String, PushPolicy> {
+        self.policies
+            .iter()
+            .map(|policy| (policy.key().clone(), *policy.value()))
+            .collect()
+    }
+
+    pub fn stats(&self) -> HashMap<String, PushStats> {
+        self.counters
+            .iter()
+            .map(|counters| {
+                let event = counters.key();
+                (
+                    event.clone(),
+                    PushStats {
+                        policy: self.resolve(event).0,
+                        received: counters.received.load(Ordering::Relaxed),
+                        emitted: counters.emitted.load(Ordering::Relaxed),
+                        coalesced: counters.coalesced.load(Ordering::Relaxed),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// 恢复所有事件的默认策略, 计数保留
+    pub fn reset(&self) {
+        self.policies.clear();
+        self.close();
+    }
+
+    /// 客户端断开时丢弃等待中的消息
+    pub fn close(&self) {
+        for slot in self.slots.iter() {
+            slot.value().cancel();
+        }
+        self.slots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate() -> (PushGate, Arc<Mutex<Vec<String>>>) {
+        let emitted = Arc::new(Mutex::new(vec![]));
+        let sink = emitted.clone();
+        let gate = PushGate::new(move |push: &Push| {
+            sink.lock().unwrap().push(push.id.clone());
+        });
+        (gate, emitted)
+    }
+
+    fn push(id: &str) -> Push {
+        let mut push = Push::new();
+        push.event = "task/update".to_string();
+        push.id = id.to_string();
+        push
+    }
+
+    async fn advance(ms: u64) {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+    }
+
+    fn taken(emitted: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+        std::mem::take(&mut *emitted.lock().unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn debounce_emits_last_after_quiet() {
+        let (gate, emitted) = gate();
+        gate.set_policy(
+            ANY_EVENT,
+            Some(PushPolicy::Debounce {
+                wait_ms: 100,
+                max_wait_ms: None,
+            }),
+        );
+        gate.push(push("1"));
+        advance(50).await;
+        gate.push(push("2"));
+        advance(80).await;
+        assert!(taken(&emitted).is_empty());
+        advance(30).await;
+        assert_eq!(taken(&emitted), vec!["2"]);
+
+        let stats = &gate.stats()["task/update"];
+        assert_eq!((stats.received, stats.emitted, stats.coalesced), (2, 1, 1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn debounce_flushes_at_max_wait() {
+        let (gate, emitted) = gate();
+        gate.set_policy(
+            ANY_EVENT,
+            Some(PushPolicy::Debounce {
+                wait_ms: 100,
+                max_wait_ms: Some(250),
+            }),
+        );
+        // 一直有新消息时也不能无限等待
+        for index in 0..10 {
+            gate.push(push(&index.to_string()));
+            advance(50).await;
+        }
+        assert_eq!(taken(&emitted), vec!["5"]);
+        advance(200).await;
+        assert_eq!(taken(&emitted), vec!["9"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn latest_throttles_to_interval() {
+        let (gate, emitted) = gate();
+        gate.set_policy(ANY_EVENT, Some(PushPolicy::Latest { interval_ms: 100 }));
+        gate.push(push("1"));
+        gate.push(push("2"));
+        gate.push(push("3"));
+        assert_eq!(taken(&emitted), vec!["1"]);
+        advance(110).await;
+        assert_eq!(taken(&emitted), vec!["3"]);
+        // 再过一个间隔没有新消息后回到空闲, 下一条立即发送
+        advance(110).await;
+        gate.push(push("4"));
+        assert_eq!(taken(&emitted), vec!["4"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn changing_policy_flushes_pending() {
+        let (gate, emitted) = gate();
+        gate.set_policy(
+            "task/update",
+            Some(PushPolicy::Debounce {
+                wait_ms: 1000,
+                max_wait_ms: None,
+            }),
+        );
+        gate.push(push("1"));
+        gate.set_policy("task/update", None);
+        assert_eq!(taken(&emitted), vec!["1"]);
+        gate.push(push("2"));
+        assert_eq!(taken(&emitted), vec!["2"]);
+    }
+
+    #[test]
+    fn debounce_max_wait_is_optional() {
+        let policy: PushPolicy =
+            serde_json::from_str(r#"{"kind": "debounce", "waitMs": 100}"#).unwrap();
+        assert_eq!(
+            policy,
+            PushPolicy::Debounce {
+                wait_ms: 100,
+                max_wait_ms: None
+            }
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{ctx_error, logger::LogContext};
+
+use super::{
+    traffic::{now_millis, Direction},
+    w_client::RecvData,
+};
+
+const RECORD_FILE_NAME: &str = "traffic";
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// 录制文件中的一行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficRecord {
+    /// 毫秒时间戳
+    pub time: u64,
+    pub direction: Direction,
+    pub address: String,
+    pub kind: String,
+    pub size: usize,
+    pub message: Value,
+    /// 原始帧的 hex, 回放时使用
+    pub raw: String,
+}
+
+impl TrafficRecord {
+    pub fn new(
+        direction: Direction,
+        address: &str,
+        payload: &[u8],
+        decoded: &Result<RecvData, String>,
+    ) -> Self {
+        let (kind, message) = match decoded {
+            Ok(data) => (data.kind().to_string(), data.json_value()),
+            Err(error) => ("invalid".to_string(), Value::String(error.clone())),
+        };
+        Self {
+            time: now_millis(),
+            direction,
+            address: address.to_string(),
+            kind,
+            size: payload.len(),
+            message,
+            raw: hex::encode(payload),
+        }
+    }
+
+    pub fn payload(&self) -> Result<Vec<u8>> {
+        Ok(hex::decode(&self.raw)?)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(optional_fields))]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordOptions {
+    /// 单个文件的最大字节数, 超过后滚动
+    #[cfg_attr(feature = "ts", ts(as = "Option<f64>"))]
+    pub max_bytes: u64,
+    /// 最多保留的文件数量 (包括正在写入的文件)
+    #[cfg_attr(feature = "ts", ts(as = "Option<usize>"))]
+    pub max_files: usize,
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+struct RecordFile {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+    options: RecordOptions,
+}
+
+impl RecordFile {
+    fn open(dir: PathBuf, options: RecordOptions) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path(&dir, 0))?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            dir,
+            file,
+            written,
+            options,
+        })
+    }
+
+    fn write(&mut self, line: &[u8]) -> Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 > self.options.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    // traffic.jsonl -> traffic.1.jsonl -> traffic.2.jsonl ..., 超出数量的直接删除
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+        let max_files = self.options.max_files.max(1);
+        let oldest = file_path(&self.dir, max_files - 1);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (0..max_files - 1).rev() {
+            let from = file_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(&from, file_path(&self.dir, index + 1))?;
+            }
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path(&self.dir, 0))?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+fn file_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(format!("{}.jsonl", RECORD_FILE_NAME)),
+        index => dir.join(format!("{}.{}.jsonl", RECORD_FILE_NAME, index)),
+    }
+}
+
+/// 协议流量录制, 默认关闭, 开启后把所有收发的帧写入滚动的 jsonl 文件
+#[derive(Default)]
+pub struct Recorder {
+    file: Mutex<Option<RecordFile>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始录制, 返回当前写入的文件
+    pub fn start(&self, dir: PathBuf, options: RecordOptions) -> Result<PathBuf> {
+        let path = file_path(&dir, 0);
+        let file = RecordFile::open(dir, options)?;
+        *self.file.lock().unwrap() = Some(file);
+        Ok(path)
+    }
+
+    pub fn stop(&self) {
+        if let Some(mut file) = self.file.lock().unwrap().take() {
+            let _ = file.file.flush();
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.file.lock().unwrap().is_some()
+    }
+
+    pub fn record(
+        &self,
+        direction: Direction,
+        address: &str,
+        payload: &[u8],
+        decoded: &Result<RecvData, String>,
+    ) {
+        let mut file = self.file.lock().unwrap();
+        if let Some(file) = file.as_mut() {
+            let record = TrafficRecord::new(direction, address, payload, decoded);
+            match serde_json::to_vec(&record) {
+                Ok(mut line) => {
+                    line.push(b'\n');
+                    if let Err(error) = file.write(&line) {
+                        ctx_error!(
+                            LogContext::new().address(address),
+                            "record traffic error: {}",
+                            error
+                        );
+                    }
+                }
+                Err(error) => ctx_error!(
+                    LogContext::new().address(address),
+                    "serialize traffic error: {}",
+                    error
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use log::error;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ctx_error;
+
+use super::{
+    recorder::TrafficRecord,
+    sink::ChannelSink,
+    traffic::Direction,
+    w_client::{RecvData, WClient},
+};
+
+/// 读取录制文件, 跳过无法解析的行
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<TrafficRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<TrafficRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(error) => error!("replay skip line {}: {}", index + 1, error),
+        }
+    }
+    Ok(records)
+}
+
+/// 回放中客户端发出的一个事件
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ReplayEvent {
+    pub event: String,
+    pub payload: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ReplayResult {
+    /// 回放的帧数
+    pub frames: usize,
+    /// 按顺序发出的事件
+    pub events: Vec<ReplayEvent>,
+}
+
+/// 把录制中从 `client` 的地址收到的帧交给它的离线副本 (`WClient::offline_copy`) 处理.
+/// 回放不会向服务端发送确认或者请求, 也不会影响页面和原客户端的集合副本,
+/// 发出的事件按顺序返回. 推送策略不复制, 每条推送都会发出.
+/// `realtime` 为 true 时按录制时的时间间隔回放.
+pub async fn replay(
+    client: &WClient,
+    records: Vec<TrafficRecord>,
+    realtime: bool,
+) -> Result<ReplayResult> {
+    let (sink, mut events) = ChannelSink::new();
+    let mut offline = client.offline_copy(Arc::new(sink));
+    let mut result = ReplayResult::default();
+    let mut last_time = None;
+    for record in records
+        .into_iter()
+        .filter(|record| record.direction == Direction::Recv && record.address == client.address)
+    {
+        if realtime {
+            if let Some(last_time) = last_time {
+                let wait = record.time.saturating_sub(last_time);
+                tokio::time::sleep(tokio::time::Duration::from_millis(wait)).await;
+            }
+            last_time = Some(record.time);
+        }
+        match RecvData::decode(&record.payload()?) {
+            Ok(data) => {
+                offline.handle_message(&data).await;
+                result.frames += 1;
+            }
+            Err(error) => ctx_error!(
+                client.log_ctx(),
+                "replay decode error at {}: {}",
+                record.time,
+                error
+            ),
+        }
+    }
+    offline.pushes.close();
+    offline.store.clear();
+    while let Ok(event) = events.try_recv() {
+        result.events.push(ReplayEvent {
+            event: event.event,
+            payload: event.payload,
+        });
+    }
+    Ok(result)
+}
@@ -0,0 +1,453 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use proto::message::Push;
+use serde::Serialize;
+
+/// 缺号后最多缓存的推送数, 超过后不再等待重发
+pub const MAX_PENDING_PUSHES: usize = 1024;
+
+/// 需要向服务端请求重发的位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeFrom {
+    pub session: String,
+    pub after: u64,
+}
+
+/// 服务端无法重发缺失的推送, 页面需要重新拉取完整数据
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ResyncNotice {
+    pub session: String,
+    /// 最后一条连续收到的推送
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub after: u64,
+    pub reason: String,
+}
+
+/// 同一个 session 内的位置, 缺号之后先到的推送按 seq 排序等待补齐
+#[derive(Debug, Default)]
+struct Track {
+    last_seq: u64,
+    pending: BTreeMap<u64, Push>,
+}
+
+enum Placed {
+    Duplicate,
+    /// 可以按顺序交出的推送, 包括之前缓存的
+    Ready(Vec<Push>),
+    /// 前面缺号, 已经缓存
+    Buffered,
+}
+
+impl Track {
+    fn place(&mut self, push: Push) -> Placed {
+        if push.seq <= self.last_seq || self.pending.contains_key(&push.seq) {
+            return Placed::Duplicate;
+        }
+        if push.seq != self.last_seq + 1 {
+            self.pending.insert(push.seq, push);
+            return Placed::Buffered;
+        }
+        self.last_seq = push.seq;
+        let mut ready = vec![push];
+        ready.extend(self.drain());
+        Placed::Ready(ready)
+    }
+
+    fn drain(&mut self) -> Vec<Push> {
+        let mut ready = vec![];
+        while let Some(push) = self.pending.remove(&(self.last_seq + 1)) {
+            self.last_seq = push.seq;
+            ready.push(push);
+        }
+        ready
+    }
+
+    /// 按顺序交出缓存的推送并跳过缺失的部分
+    fn skip_gap(&mut self) -> Vec<Push> {
+        let ready: Vec<Push> = std::mem::take(&mut self.pending).into_values().collect();
+        if let Some(last) = ready.last() {
+            self.last_seq = last.seq;
+        }
+        ready
+    }
+}
+
+/// 切换 session 之前的旧 session, 等待服务端重发断线期间的推送
+#[derive(Debug)]
+struct Previous {
+    session: String,
+    track: Track,
+}
+
+/// 重发请求成功返回后的处理结果
+#[derive(Debug, Default)]
+pub struct Resumed {
+    /// 可以按顺序交给页面的推送
+    pub ready: Vec<Push>,
+    /// 重发之后仍然缺号, 缺失的部分已经跳过, 需要通知页面重新拉取
+    pub gap: bool,
+    /// 新 session 开头缺号, 需要再请求重发
+    pub next: Option<ResumeFrom>,
+}
+
+/// 最多记住的已经结束的旧 session
+const RETIRED_SESSIONS: usize = 8;
+
+/// 单个连接上推送序号的跟踪状态. 每个 session 的 seq 从 1 开始,
+/// 切换 session 后先补齐旧 session, 期间新 session 的推送都先缓存, 保证页面收到的顺序
+#[derive(Debug, Default)]
+pub struct PushCursor {
+    session: String,
+    track: Track,
+    // 已经发出重发请求, 等待结果
+    resuming: Option<ResumeFrom>,
+    previous: Option<Previous>,
+    // 已经结束的旧 session 以及它是否在补齐之前被放弃, 之后再收到它们的推送直接丢弃
+    retired: VecDeque<(String, bool)>,
+}
+
+impl PushCursor {
+    /// 处理一条推送, 返回可以按顺序交给页面的推送和需要发出的重发请求
+    pub fn accept(&mut self, push: Push) -> (Vec<Push>, Option<ResumeFrom>) {
+        // 服务端没有序号时保持原来的行为
+        if push.seq == 0 {
+            return (vec![push], None);
+        }
+        if self.session.is_empty() {
+            self.session = push.session.clone();
+        }
+        if self.is_retired(&push.session) {
+            return (vec![], None);
+        }
+        if let Some(previous) = self.previous.as_mut() {
+            if previous.session == push.session {
+                // 服务端重发的旧 session 推送, 缺号的部分等重发结束后处理
+                return match previous.track.place(push) {
+                    Placed::Ready(ready) => (ready, None),
+                    _ => (vec![], None),
+                };
+            }
+        }
+        if push.session != self.session {
+            return self.switch_session(push);
+        }
+        if self.previous.is_some() {
+            // 旧 session 补齐之前新 session 的推送先缓存
+            if push.seq > self.track.last_seq {
+                self.track.pending.insert(push.seq, push);
+            }
+            return (self.overflow().unwrap_or_default(), None);
+        }
+        match self.track.place(push) {
+            Placed::Ready(ready) => {
+                if self.track.pending.is_empty() {
+                    self.resuming = None;
+                }
+                (ready, None)
+            }
+            Placed::Duplicate => (vec![], None),
+            Placed::Buffered => match self.overflow() {
+                Some(ready) => (ready, None),
+                None => (vec![], self.begin_resume()),
+            },
+        }
+    }
+
+    /// 重连后服务端分配了新的 session, 请求重发旧 session 中没有收到的推送
+    fn switch_session(&mut self, push: Push) -> (Vec<Push>, Option<ResumeFrom>) {
+        // 上一个旧 session 还没有补齐又切换了, 放弃它, 它的重发结果返回时通知页面
+        let ready = match self.previous.take() {
+            Some(previous) => self.retire(previous, true),
+            None => vec![],
+        };
+        let previous = Previous {
+            session: std::mem::replace(&mut self.session, push.session.clone()),
+            track: std::mem::take(&mut self.track),
+        };
+        let resume = ResumeFrom {
+            session: previous.session.clone(),
+            after: previous.track.last_seq,
+        };
+        self.previous = Some(previous);
+        self.track.pending.insert(push.seq, push);
+        self.resuming = Some(resume.clone());
+        (ready, Some(resume))
+    }
+
+    fn retire(&mut self, mut previous: Previous, abandoned: bool) -> Vec<Push> {
+        if self.retired.len() == RETIRED_SESSIONS {
+            self.retired.pop_front();
+        }
+        self.retired.push_back((previous.session, abandoned));
+        previous.track.skip_gap()
+    }
+
+    fn is_retired(&self, session: &str) -> bool {
+        self.retired.iter().any(|(retired, _)| retired == session)
+    }
+
+    /// 缓存太多时不再等待重发
+    fn overflow(&mut self) -> Option<Vec<Push>> {
+        let pending = self.track.pending.len()
+            + self
+                .previous
+                .as_ref()
+                .map_or(0, |previous| previous.track.pending.len());
+        if pending > MAX_PENDING_PUSHES {
+            Some(self.give_up())
+        } else {
+            None
+        }
+    }
+
+    fn begin_resume(&mut self) -> Option<ResumeFrom> {
+        if self.resuming.is_some() {
+            return None;
+        }
+        let resume = ResumeFrom {
+            session: self.session.clone(),
+            after: self.track.last_seq,
+        };
+        self.resuming = Some(resume.clone());
+        Some(resume)
+    }
+
+    /// 服务端无法重发, 按顺序交出所有缓存的推送并跳过缺失的部分
+    pub fn give_up(&mut self) -> Vec<Push> {
+        self.resuming = None;
+        let mut ready = match self.previous.take() {
+            Some(previous) => self.retire(previous, false),
+            None => vec![],
+        };
+        ready.extend(self.track.skip_gap());
+        ready
+    }
+
+    /// 重发请求成功返回, 服务端已经在响应之前重发了所有还保留的推送
+    pub fn resumed(&mut self, resume: &ResumeFrom) -> Resumed {
+        if self.resuming.as_ref() == Some(resume) {
+            self.resuming = None;
+        }
+        let previous = match self.previous.take() {
+            Some(previous) if previous.session == resume.session => previous,
+            other => {
+                self.previous = other;
+                if self.is_retired(&resume.session) {
+                    // 补齐之前又切换了 session 时缺失的部分已经跳过
+                    let abandoned = self
+                        .retired
+                        .iter()
+                        .any(|(retired, abandoned)| retired == &resume.session && *abandoned);
+                    return Resumed {
+                        gap: abandoned,
+                        ..Default::default()
+                    };
+                }
+                if self.previous.is_some() || self.track.pending.is_empty() {
+                    return Resumed::default();
+                }
+                return Resumed {
+                    ready: self.give_up(),
+                    gap: true,
+                    next: None,
+                };
+            }
+        };
+        // 旧 session 结束, 按顺序交出新 session 缓存的推送, 开头缺号时再请求重发
+        let gap = !previous.track.pending.is_empty();
+        let mut ready = self.retire(previous, false);
+        ready.extend(self.track.drain());
+        let next = if self.track.pending.is_empty() {
+            None
+        } else {
+            self.begin_resume()
+        };
+        Resumed { ready, gap, next }
+    }
+
+    pub fn position(&self) -> ResumeFrom {
+        ResumeFrom {
+            session: self.session.clone(),
+            after: self.track.last_seq,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(session: &str, seq: u64) -> Push {
+        let mut push = Push::new();
+        push.session = session.to_string();
+        push.seq = seq;
+        push
+    }
+
+    fn seqs(pushes: &[Push]) -> Vec<(String, u64)> {
+        pushes
+            .iter()
+            .map(|push| (push.session.clone(), push.seq))
+            .collect()
+    }
+
+    fn resume(session: &str, after: u64) -> Option<ResumeFrom> {
+        Some(ResumeFrom {
+            session: session.to_string(),
+            after,
+        })
+    }
+
+    #[test]
+    fn in_order_and_duplicates() {
+        let mut cursor = PushCursor::default();
+        assert_eq!(cursor.accept(push("a", 1)).0.len(), 1);
+        assert_eq!(cursor.accept(push("a", 2)).0.len(), 1);
+        assert!(cursor.accept(push("a", 2)).0.is_empty());
+        assert!(cursor.accept(push("a", 1)).0.is_empty());
+        assert_eq!(cursor.position(), resume("a", 2).unwrap());
+    }
+
+    #[test]
+    fn pushes_without_seq_pass_through() {
+        let mut cursor = PushCursor::default();
+        for _ in 0..3 {
+            let (ready, resume) = cursor.accept(push("", 0));
+            assert_eq!(ready.len(), 1);
+            assert!(resume.is_none());
+        }
+    }
+
+    #[test]
+    fn first_session_must_start_at_one() {
+        let mut cursor = PushCursor::default();
+        let (ready, next) = cursor.accept(push("a", 3));
+        assert!(ready.is_empty());
+        assert_eq!(next, resume("a", 0));
+    }
+
+    #[test]
+    fn gap_is_filled_by_replay() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        let (ready, next) = cursor.accept(push("a", 4));
+        assert!(ready.is_empty());
+        assert_eq!(next, resume("a", 1));
+        // 同一个缺口只请求一次
+        assert_eq!(cursor.accept(push("a", 5)).1, None);
+
+        assert_eq!(seqs(&cursor.accept(push("a", 2)).0), vec![("a".into(), 2)]);
+        let (ready, _) = cursor.accept(push("a", 3));
+        assert_eq!(
+            seqs(&ready),
+            vec![("a".into(), 3), ("a".into(), 4), ("a".into(), 5)]
+        );
+        let resumed = cursor.resumed(&resume("a", 1).unwrap());
+        assert!(!resumed.gap);
+        assert!(resumed.ready.is_empty());
+    }
+
+    #[test]
+    fn unfilled_gap_is_skipped() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        cursor.accept(push("a", 3));
+        let resumed = cursor.resumed(&resume("a", 1).unwrap());
+        assert!(resumed.gap);
+        assert_eq!(seqs(&resumed.ready), vec![("a".into(), 3)]);
+        assert_eq!(seqs(&cursor.accept(push("a", 4)).0), vec![("a".into(), 4)]);
+    }
+
+    #[test]
+    fn new_session_waits_for_old_replay() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        cursor.accept(push("a", 2));
+
+        // 重连后先收到新 session 的推送, 不能排在旧 session 的重发前面
+        let (ready, next) = cursor.accept(push("b", 1));
+        assert!(ready.is_empty());
+        assert_eq!(next, resume("a", 2));
+        assert!(cursor.accept(push("b", 2)).0.is_empty());
+
+        assert_eq!(seqs(&cursor.accept(push("a", 3)).0), vec![("a".into(), 3)]);
+        assert_eq!(seqs(&cursor.accept(push("a", 4)).0), vec![("a".into(), 4)]);
+
+        let resumed = cursor.resumed(&resume("a", 2).unwrap());
+        assert!(!resumed.gap);
+        assert!(resumed.next.is_none());
+        assert_eq!(seqs(&resumed.ready), vec![("b".into(), 1), ("b".into(), 2)]);
+        // 旧 session 结束后的重复推送直接丢弃
+        assert!(cursor.accept(push("a", 5)).0.is_empty());
+        assert_eq!(seqs(&cursor.accept(push("b", 3)).0), vec![("b".into(), 3)]);
+    }
+
+    #[test]
+    fn new_session_start_is_checked() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        cursor.accept(push("b", 5));
+        let resumed = cursor.resumed(&resume("a", 1).unwrap());
+        assert!(!resumed.gap);
+        assert!(resumed.ready.is_empty());
+        assert_eq!(resumed.next, resume("b", 0));
+
+        let (ready, _) = cursor.accept(push("b", 1));
+        assert_eq!(seqs(&ready), vec![("b".into(), 1)]);
+    }
+
+    #[test]
+    fn old_session_gap_is_reported_after_replay() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        cursor.accept(push("b", 1));
+        // 重发中仍然缺少 a#2
+        assert!(cursor.accept(push("a", 3)).0.is_empty());
+        let resumed = cursor.resumed(&resume("a", 1).unwrap());
+        assert!(resumed.gap);
+        assert_eq!(seqs(&resumed.ready), vec![("a".into(), 3), ("b".into(), 1)]);
+    }
+
+    #[test]
+    fn give_up_releases_everything_in_order() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        cursor.accept(push("a", 3));
+        cursor.accept(push("b", 1));
+        cursor.accept(push("b", 2));
+        assert_eq!(
+            seqs(&cursor.give_up()),
+            vec![("a".into(), 3), ("b".into(), 1), ("b".into(), 2)]
+        );
+        assert_eq!(seqs(&cursor.accept(push("b", 3)).0), vec![("b".into(), 3)]);
+    }
+
+    #[test]
+    fn switching_again_abandons_the_older_session() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        cursor.accept(push("b", 1));
+        let (ready, next) = cursor.accept(push("c", 1));
+        assert!(ready.is_empty());
+        assert_eq!(next, resume("b", 0));
+        // a 在补齐之前被放弃, 需要通知页面
+        assert!(cursor.resumed(&resume("a", 1).unwrap()).gap);
+        let resumed = cursor.resumed(&resume("b", 0).unwrap());
+        assert_eq!(seqs(&resumed.ready), vec![("b".into(), 1), ("c".into(), 1)]);
+    }
+
+    #[test]
+    fn too_many_pending_pushes_stop_waiting() {
+        let mut cursor = PushCursor::default();
+        cursor.accept(push("a", 1));
+        let mut released = vec![];
+        for seq in 3..(MAX_PENDING_PUSHES as u64 + 4) {
+            released.extend(cursor.accept(push("a", seq)).0);
+        }
+        assert_eq!(released.len(), MAX_PENDING_PUSHES + 1);
+        assert_eq!(released.first().unwrap().seq, 3);
+    }
+}
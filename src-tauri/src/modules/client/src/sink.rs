@@ -0,0 +1,51 @@
+use serde_json::Value;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// 客户端事件的去处, 桌面端发往窗口, 命令行和测试可以自己实现
+pub trait EventSink: Send + Sync {
+    /// 发给所有监听者
+    fn emit_all(&self, event: &str, payload: &Value) -> Result<(), String>;
+
+    /// 发给指定的监听者, 不存在时直接忽略
+    fn emit_to(&self, target: &str, event: &str, payload: &Value) -> Result<(), String>;
+}
+
+/// `ChannelSink` 收到的一个事件, `target` 为空表示发给所有监听者
+#[derive(Debug, Clone)]
+pub struct SinkEvent {
+    pub target: Option<String>,
+    pub event: String,
+    pub payload: Value,
+}
+
+/// 把事件放进通道, 由调用方自己读取
+pub struct ChannelSink {
+    sender: UnboundedSender<SinkEvent>,
+}
+
+impl ChannelSink {
+    pub fn new() -> (Self, UnboundedReceiver<SinkEvent>) {
+        let (sender, receiver) = unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    fn send(&self, target: Option<&str>, event: &str, payload: &Value) -> Result<(), String> {
+        self.sender
+            .send(SinkEvent {
+                target: target.map(|target| target.to_string()),
+                event: event.to_string(),
+                payload: payload.clone(),
+            })
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl EventSink for ChannelSink {
+    fn emit_all(&self, event: &str, payload: &Value) -> Result<(), String> {
+        self.send(None, event, payload)
+    }
+
+    fn emit_to(&self, target: &str, event: &str, payload: &Value) -> Result<(), String> {
+        self.send(Some(target), event, payload)
+    }
+}
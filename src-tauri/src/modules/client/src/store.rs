@@ -0,0 +1,829 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// 一个由服务端维护的集合, 客户端按 `key` 字段以 id 保存副本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(optional_fields))]
+#[serde(rename_all = "camelCase", default)]
+pub struct CollectionSpec {
+    pub name: String,
+    /// 元素中作为 id 的字段
+    #[cfg_attr(feature = "ts", ts(as = "Option<String>"))]
+    pub key: String,
+    /// 推送完整列表的事件, 例如 `task-list/update`
+    pub snapshot_event: Option<String>,
+    /// 完整列表在数据中的字段, 为空时数据本身就是列表
+    pub list_field: Option<String>,
+    /// 推送增量修改的事件
+    pub patch_event: Option<String>,
+    /// 版本不连续时重新拉取完整列表的地址, 例如 `/task/list`
+    pub resync_url: Option<String>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<Value>"))]
+    pub resync_data: Value,
+}
+
+impl Default for CollectionSpec {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            key: "id".to_string(),
+            snapshot_event: None,
+            list_field: None,
+            patch_event: None,
+            resync_url: None,
+            resync_data: Value::Null,
+        }
+    }
+}
+
+/// 单条增量修改
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum PatchOp {
+    /// 插入或整体替换
+    Insert {
+        value: Value,
+    },
+    /// 按字段合并, 值为 null 的字段会被删除
+    Update {
+        id: Value,
+        value: Value,
+    },
+    Delete {
+        id: Value,
+    },
+    /// 对单个元素应用 JSON Patch (RFC 6902)
+    Patch {
+        id: Value,
+        patch: json_patch::Patch,
+    },
+}
+
+/// `patch_event` 推送的数据
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatchSet {
+    pub version: Option<u64>,
+    pub ops: Vec<PatchOp>,
+}
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("collection {0} not registered")]
+    NotFound(String),
+    #[error("invalid snapshot: {0}")]
+    InvalidSnapshot(String),
+    #[error("invalid patch: {0}")]
+    InvalidPatch(String),
+    #[error("item {0} not found")]
+    ItemNotFound(String),
+    /// 拉取完整列表失败, `retry` 为 true 时修改仍在缓存中, 应该再拉取一次
+    #[error("resync failed: {reason}")]
+    ResyncFailed { reason: String, retry: bool },
+}
+
+/// 拉取完整列表最多尝试的次数, 之后集合标记为过期, 收到下一条修改时再拉取
+pub const RESYNC_ATTEMPTS: u32 = 3;
+
+/// 查询条件, 所有条件同时满足
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "ts", ts(optional_fields))]
+#[serde(rename_all = "camelCase", default)]
+pub struct StoreFilter {
+    /// 字段等于给定值, 给定值为数组时表示字段等于其中之一
+    #[serde(rename = "where")]
+    #[cfg_attr(feature = "ts", ts(as = "Option<HashMap<String, Value>>"))]
+    pub where_: HashMap<String, Value>,
+    /// 任意字符串字段包含该文本
+    pub search: Option<String>,
+    pub sort_by: Option<String>,
+    #[cfg_attr(feature = "ts", ts(as = "Option<bool>"))]
+    pub desc: bool,
+    #[cfg_attr(feature = "ts", ts(as = "Option<usize>"))]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl StoreFilter {
+    fn matches(&self, item: &Value) -> bool {
+        let fields_match = self.where_.iter().all(|(field, expected)| {
+            let actual = item.get(field).unwrap_or(&Value::Null);
+            match expected {
+                Value::Array(candidates) => candidates.contains(actual),
+                expected => expected == actual,
+            }
+        });
+        let search_match = match &self.search {
+            Some(text) => item
+                .as_object()
+                .map(|object| {
+                    object
+                        .values()
+                        .any(|value| value.as_str().map_or(false, |value| value.contains(text)))
+                })
+                .unwrap_or(false),
+            None => true,
+        };
+        fields_match && search_match
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// 过滤后的集合视图
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct StoreView {
+    pub collection: String,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub version: u64,
+    /// 过滤后, 分页前的总数
+    pub total: usize,
+    pub items: Vec<Value>,
+}
+
+/// 新建的订阅和订阅时的视图
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct StoreSubscription {
+    pub subscription: String,
+    pub view: StoreView,
+}
+
+/// id 统一转成字符串作为 map 的 key, 数字和字符串 id 都可以使用
+fn id_key(id: &Value) -> Option<String> {
+    match id {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// RFC 7396 风格的合并
+fn merge(target: &mut Value, patch: &Value) {
+    match (target.as_object_mut(), patch.as_object()) {
+        (Some(target), Some(patch)) => {
+            for (field, value) in patch {
+                if value.is_null() {
+                    target.remove(field);
+                } else {
+                    merge(target.entry(field.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+struct Collection {
+    spec: CollectionSpec,
+    version: u64,
+    // 是否已经拿到过服务端的版本号, 之后才检查版本是否连续
+    versioned: bool,
+    // 正在重新拉取完整列表, 期间收到的修改先缓存
+    resyncing: bool,
+    buffered: Vec<PatchSet>,
+    // 本次拉取失败的次数
+    failures: u32,
+    // 拉取失败后放弃, 副本已经过期, 修改不能再直接应用
+    stale: bool,
+    items: BTreeMap<String, Value>,
+}
+
+impl Collection {
+    fn new(spec: CollectionSpec) -> Self {
+        Self {
+            spec,
+            version: 0,
+            versioned: false,
+            resyncing: false,
+            buffered: vec![],
+            failures: 0,
+            stale: false,
+            items: BTreeMap::new(),
+        }
+    }
+
+    fn replace(&mut self, data: &Value) -> Result<(), StoreError> {
+        let list = match &self.spec.list_field {
+            Some(field) => data.get(field),
+            None => Some(data),
+        };
+        let list = list
+            .and_then(|list| list.as_array())
+            .ok_or_else(|| StoreError::InvalidSnapshot("list not found".to_string()))?;
+        let mut items = BTreeMap::new();
+        for item in list {
+            let id = item
+                .get(&self.spec.key)
+                .and_then(id_key)
+                .ok_or_else(|| StoreError::InvalidSnapshot(format!("missing {}", self.spec.key)))?;
+            items.insert(id, item.clone());
+        }
+        self.items = items;
+        match data.get("version").and_then(Value::as_u64) {
+            Some(version) => {
+                self.version = version;
+                self.versioned = true;
+            }
+            None => {
+                self.version += 1;
+                self.versioned = false;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_op(&mut self, op: &PatchOp) -> Result<(), StoreError> {
+        match op {
+            PatchOp::Insert { value } => {
+                let id = value.get(&self.spec.key).and_then(id_key).ok_or_else(|| {
+                    StoreError::InvalidPatch(format!("missing {}", self.spec.key))
+                })?;
+                self.items.insert(id, value.clone());
+            }
+            PatchOp::Update { id, value } => {
+                let id = id_key(id).ok_or_else(|| StoreError::InvalidPatch(id.to_string()))?;
+                let item = self
+                    .items
+                    .get_mut(&id)
+                    .ok_or(StoreError::ItemNotFound(id))?;
+                merge(item, value);
+            }
+            PatchOp::Delete { id } => {
+                let id = id_key(id).ok_or_else(|| StoreError::InvalidPatch(id.to_string()))?;
+                self.items.remove(&id).ok_or(StoreError::ItemNotFound(id))?;
+            }
+            PatchOp::Patch { id, patch } => {
+                let id = id_key(id).ok_or_else(|| StoreError::InvalidPatch(id.to_string()))?;
+                let item = self
+                    .items
+                    .get_mut(&id)
+                    .ok_or(StoreError::ItemNotFound(id))?;
+                // 先在副本上修改, 失败时不会留下修改了一半的元素
+                let mut patched = item.clone();
+                json_patch::patch(&mut patched, patch)
+                    .map_err(|error| StoreError::InvalidPatch(error.to_string()))?;
+                *item = patched;
+            }
+        }
+        Ok(())
+    }
+
+    /// 应用一组修改, 版本不连续或修改失败时返回 false, 需要重新拉取
+    fn apply(&mut self, patch: PatchSet) -> Result<bool, StoreError> {
+        if self.resyncing {
+            self.buffered.push(patch);
+            return Ok(true);
+        }
+        if self.stale {
+            return Ok(false);
+        }
+        if let Some(version) = patch.version {
+            if self.versioned && version <= self.version {
+                // 重复或过期的修改
+                return Ok(true);
+            }
+            if self.versioned && version != self.version + 1 {
+                return Ok(false);
+            }
+        }
+        // 一组修改要么全部生效要么全部不生效
+        let backup = self.items.clone();
+        for op in &patch.ops {
+            if let Err(error) = self.apply_op(op) {
+                self.items = backup;
+                return Err(error);
+            }
+        }
+        match patch.version {
+            Some(version) => {
+                self.version = version;
+                self.versioned = true;
+            }
+            None => self.version += 1,
+        }
+        Ok(true)
+    }
+
+    fn view(&self, filter: &StoreFilter) -> StoreView {
+        let mut items: Vec<&Value> = self
+            .items
+            .values()
+            .filter(|item| filter.matches(item))
+            .collect();
+        if let Some(field) = &filter.sort_by {
+            items.sort_by(|a, b| {
+                let ordering = compare(
+                    a.get(field).unwrap_or(&Value::Null),
+                    b.get(field).unwrap_or(&Value::Null),
+                );
+                if filter.desc {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        let total = items.len();
+        let items = items
+            .into_iter()
+            .skip(filter.offset)
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        StoreView {
+            collection: self.spec.name.clone(),
+            version: self.version,
+            total,
+            items,
+        }
+    }
+}
+
+/// 处理一条 push 之后需要调用方做的事
+#[derive(Debug)]
+pub enum StoreAction {
+    None,
+    /// 集合发生了变化, 已经通知了订阅者
+    Changed(String),
+    /// 需要请求 `resync_url` 并调用 `StateStore::resync_done`
+    Resync(CollectionSpec),
+}
+
+struct Subscription {
+    collection: String,
+    filter: StoreFilter,
+}
+
+type Notify = Arc<dyn Fn(&str, &StoreView) + Send + Sync>;
+
+/// 服务端集合在客户端的副本, 由 push 维护, 页面通过查询和订阅读取
+pub struct StateStore {
+    collections: DashMap<String, Collection>,
+    // 事件名 -> 集合名
+    events: DashMap<String, String>,
+    subscriptions: DashMap<String, Subscription>,
+    notify: Notify,
+}
+
+impl StateStore {
+    pub fn new(notify: impl Fn(&str, &StoreView) + Send + Sync + 'static) -> Self {
+        Self {
+            collections: DashMap::new(),
+            events: DashMap::new(),
+            subscriptions: DashMap::new(),
+            notify: Arc::new(notify),
+        }
+    }
+
+    /// 注册集合, 同名集合会被替换并清空
+    pub fn register(&self, spec: CollectionSpec) {
+        self.unregister(&spec.name);
+        for event in [&spec.snapshot_event, &spec.patch_event]
+            .into_iter()
+            .flatten()
+        {
+            self.events.insert(event.clone(), spec.name.clone());
+        }
+        self.collections
+            .insert(spec.name.clone(), Collection::new(spec));
+    }
+
+    pub fn unregister(&self, name: &str) {
+        self.collections.remove(name);
+        self.events.retain(|_, collection| collection != name);
+    }
+
+    pub fn specs(&self) -> Vec<CollectionSpec> {
+        self.collections
+            .iter()
+            .map(|collection| collection.spec.clone())
+            .collect()
+    }
+
+    /// 处理服务端推送, 不属于任何集合的事件直接忽略
+    pub fn apply_push(&self, event: &str, data: &Value) -> Result<StoreAction, StoreError> {
+        let name = match self.events.get(event) {
+            Some(name) => name.clone(),
+            None => return Ok(StoreAction::None),
+        };
+        let action = {
+            let mut collection = self
+                .collections
+                .get_mut(&name)
+                .ok_or_else(|| StoreError::NotFound(name.clone()))?;
+            if collection.spec.snapshot_event.as_deref() == Some(event) {
+                collection.replace(data)?;
+                collection.resyncing = false;
+                collection.stale = false;
+                collection.buffered.clear();
+                StoreAction::Changed(name.clone())
+            } else {
+                let patch: PatchSet = serde_json::from_value(data.clone())
+                    .map_err(|error| StoreError::InvalidPatch(error.to_string()))?;
+                let version = collection.version;
+                match collection.apply(patch) {
+                    Ok(true) if collection.version == version => StoreAction::None,
+                    Ok(true) => StoreAction::Changed(name.clone()),
+                    Ok(false) | Err(_) => Self::begin_resync(&mut collection),
+                }
+            }
+        };
+        if let StoreAction::Changed(name) = &action {
+            self.notify_collection(name);
+        }
+        Ok(action)
+    }
+
+    fn begin_resync(collection: &mut Collection) -> StoreAction {
+        match &collection.spec.resync_url {
+            Some(_) if !collection.resyncing => {
+                collection.resyncing = true;
+                collection.failures = 0;
+                collection.buffered.clear();
+                StoreAction::Resync(collection.spec.clone())
+            }
+            Some(_) => StoreAction::None,
+            // 没有配置拉取地址时只能等下一次完整推送
+            None => {
+                collection.versioned = false;
+                StoreAction::None
+            }
+        }
+    }
+
+    /// 主动重新拉取
+    pub fn request_resync(&self, name: &str) -> Result<StoreAction, StoreError> {
+        let mut collection = self
+            .collections
+            .get_mut(name)
+            .ok_or_else(|| StoreError::NotFound(name.to_string()))?;
+        collection.resyncing = false;
+        Ok(Self::begin_resync(&mut collection))
+    }
+
+    /// 完整列表拉取结束, 再应用拉取期间缓存的修改.
+    /// 失败时保留缓存的修改并返回 `ResyncFailed`, 多次失败后放弃并把集合标记为过期
+    pub fn resync_done(&self, name: &str, data: Result<Value, Value>) -> Result<(), StoreError> {
+        {
+            let mut collection = self
+                .collections
+                .get_mut(name)
+                .ok_or_else(|| StoreError::NotFound(name.to_string()))?;
+            let replaced = match data {
+                Ok(data) => collection.replace(&data).map_err(|error| error.to_string()),
+                Err(Value::String(error)) => Err(error),
+                Err(error) => Err(error.to_string()),
+            };
+            if let Err(reason) = replaced {
+                collection.failures += 1;
+                let retry = collection.failures < RESYNC_ATTEMPTS;
+                if !retry {
+                    collection.resyncing = false;
+                    collection.stale = true;
+                    collection.buffered.clear();
+                }
+                return Err(StoreError::ResyncFailed { reason, retry });
+            }
+            let buffered = std::mem::take(&mut collection.buffered);
+            collection.resyncing = false;
+            collection.stale = false;
+            for patch in buffered {
+                // 快照之后的修改必须连续, 否则保留快照等下一次检查
+                if !matches!(collection.apply(patch), Ok(true)) {
+                    break;
+                }
+            }
+        }
+        self.notify_collection(name);
+        Ok(())
+    }
+
+    pub fn query(&self, name: &str, filter: &StoreFilter) -> Result<StoreView, StoreError> {
+        self.collections
+            .get(name)
+            .map(|collection| collection.view(filter))
+            .ok_or_else(|| StoreError::NotFound(name.to_string()))
+    }
+
+    /// 订阅过滤后的视图, 之后每次集合变化都会推送. 当前视图在返回值中, 不会推送.
+    /// 先登记订阅再取视图, 两者之间的变化也会推送, 页面按 `version` 去重
+    pub fn subscribe(
+        &self,
+        name: &str,
+        filter: StoreFilter,
+    ) -> Result<StoreSubscription, StoreError> {
+        let id = Uuid::new_v4().to_string();
+        self.subscriptions.insert(
+            id.clone(),
+            Subscription {
+                collection: name.to_string(),
+                filter: filter.clone(),
+            },
+        );
+        match self.query(name, &filter) {
+            Ok(view) => Ok(StoreSubscription {
+                subscription: id,
+                view,
+            }),
+            Err(error) => {
+                self.subscriptions.remove(&id);
+                Err(error)
+            }
+        }
+    }
+
+    pub fn unsubscribe(&self, id: &str) -> bool {
+        self.subscriptions.remove(id).is_some()
+    }
+
+    fn notify_collection(&self, name: &str) {
+        let collection = match self.collections.get(name) {
+            Some(collection) => collection,
+            None => return,
+        };
+        let views: Vec<(String, StoreView)> = self
+            .subscriptions
+            .iter()
+            .filter(|subscription| subscription.collection == name)
+            .map(|subscription| {
+                (
+                    subscription.key().clone(),
+                    collection.view(&subscription.filter),
+                )
+            })
+            .collect();
+        drop(collection);
+        for (id, view) in views {
+            (self.notify)(&id, &view);
+        }
+    }
+
+    pub fn clear_subscriptions(&self) {
+        self.subscriptions.clear();
+    }
+
+    pub fn clear(&self) {
+        self.subscriptions.clear();
+        self.collections.clear();
+        self.events.clear();
+    }
+}
+
+/// 把视图和订阅 id 合成推送给页面的数据
+pub fn subscription_payload(id: &str, view: &StoreView) -> Value {
+    let mut payload = match serde_json::to_value(view) {
+        Ok(Value::Object(payload)) => payload,
+        _ => Map::new(),
+    };
+    payload.insert("subscription".to_string(), Value::String(id.to_string()));
+    Value::Object(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn store() -> StateStore {
+        let store = StateStore::new(|_, _| {});
+        store.register(CollectionSpec {
+            name: "tasks".to_string(),
+            snapshot_event: Some("task-list/update".to_string()),
+            list_field: Some("list".to_string()),
+            patch_event: Some("task-list/patch".to_string()),
+            resync_url: Some("/task/list".to_string()),
+            ..CollectionSpec::default()
+        });
+        store
+    }
+
+    fn snapshot(version: u64, names: &[&str]) -> Value {
+        let list: Vec<Value> = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| json!({ "id": id + 1, "name": name }))
+            .collect();
+        json!({ "version": version, "list": list })
+    }
+
+    fn names(store: &StateStore) -> Vec<String> {
+        store
+            .query("tasks", &StoreFilter::default())
+            .unwrap()
+            .items
+            .iter()
+            .map(|item| item["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn patch(store: &StateStore, version: u64, ops: Value) -> StoreAction {
+        store
+            .apply_push(
+                "task-list/patch",
+                &json!({ "version": version, "ops": ops }),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn snapshot_and_patches() {
+        let store = store();
+        store
+            .apply_push("task-list/update", &snapshot(1, &["a", "b"]))
+            .unwrap();
+        let action = patch(
+            &store,
+            2,
+            json!([
+                { "op": "update", "id": 1, "value": { "name": "a2" } },
+                { "op": "delete", "id": 2 },
+                { "op": "insert", "value": { "id": 3, "name": "c" } }
+            ]),
+        );
+        assert!(matches!(action, StoreAction::Changed(_)));
+        assert_eq!(names(&store), vec!["a2", "c"]);
+        // 重复的版本直接忽略
+        assert!(matches!(
+            patch(&store, 2, json!([{ "op": "delete", "id": 3 }])),
+            StoreAction::None
+        ));
+        assert_eq!(names(&store), vec!["a2", "c"]);
+    }
+
+    #[test]
+    fn json_patch_is_atomic() {
+        let store = store();
+        store
+            .apply_push("task-list/update", &snapshot(1, &["a"]))
+            .unwrap();
+        patch(
+            &store,
+            2,
+            json!([{ "op": "patch", "id": 1, "patch": [
+                { "op": "replace", "path": "/name", "value": "b" }
+            ] }]),
+        );
+        assert_eq!(names(&store), vec!["b"]);
+
+        // 第二个操作失败时第一个也不生效, 并且需要重新拉取
+        let action = patch(
+            &store,
+            3,
+            json!([{ "op": "patch", "id": 1, "patch": [
+                { "op": "replace", "path": "/name", "value": "c" },
+                { "op": "test", "path": "/name", "value": "x" }
+            ] }]),
+        );
+        assert!(matches!(action, StoreAction::Resync(_)));
+        assert_eq!(names(&store), vec!["b"]);
+    }
+
+    #[test]
+    fn patches_during_resync_are_buffered() {
+        let store = store();
+        store
+            .apply_push("task-list/update", &snapshot(1, &["a"]))
+            .unwrap();
+        assert!(matches!(
+            patch(&store, 3, json!([])),
+            StoreAction::Resync(_)
+        ));
+        let action = patch(
+            &store,
+            4,
+            json!([{ "op": "insert", "value": { "id": 2, "name": "b" } }]),
+        );
+        assert!(matches!(action, StoreAction::None));
+        assert_eq!(names(&store), vec!["a"]);
+
+        store.resync_done("tasks", Ok(snapshot(3, &["a"]))).unwrap();
+        assert_eq!(names(&store), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn failed_resync_keeps_buffer() {
+        let store = store();
+        store
+            .apply_push("task-list/update", &snapshot(1, &["a"]))
+            .unwrap();
+        patch(&store, 3, json!([]));
+        patch(
+            &store,
+            4,
+            json!([{ "op": "insert", "value": { "id": 2, "name": "b" } }]),
+        );
+
+        let error = store
+            .resync_done("tasks", Err(json!("timeout")))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            StoreError::ResyncFailed { retry: true, .. }
+        ));
+        // 拉取失败时仍然在缓存修改
+        patch(
+            &store,
+            5,
+            json!([{ "op": "insert", "value": { "id": 3, "name": "c" } }]),
+        );
+        store.resync_done("tasks", Ok(snapshot(3, &["a"]))).unwrap();
+        assert_eq!(names(&store), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn resync_gives_up_and_marks_stale() {
+        let store = store();
+        store
+            .apply_push("task-list/update", &snapshot(1, &["a"]))
+            .unwrap();
+        patch(&store, 3, json!([]));
+        for attempt in 1..=RESYNC_ATTEMPTS {
+            let error = store
+                .resync_done("tasks", Ok(json!({ "version": 3 })))
+                .unwrap_err();
+            let retry = matches!(error, StoreError::ResyncFailed { retry: true, .. });
+            assert_eq!(retry, attempt < RESYNC_ATTEMPTS);
+        }
+        // 过期的副本不再直接应用修改, 而是重新拉取
+        let action = patch(
+            &store,
+            4,
+            json!([{ "op": "insert", "value": { "id": 2, "name": "b" } }]),
+        );
+        assert!(matches!(action, StoreAction::Resync(_)));
+        assert_eq!(names(&store), vec!["a"]);
+        store
+            .resync_done("tasks", Ok(snapshot(4, &["a", "b"])))
+            .unwrap();
+        assert!(matches!(
+            patch(&store, 5, json!([])),
+            StoreAction::Changed(_)
+        ));
+    }
+
+    #[test]
+    fn subscribe_returns_current_view() {
+        let notified = Arc::new(Mutex::new(Vec::new()));
+        let sink = notified.clone();
+        let store = StateStore::new(move |id, view| {
+            sink.lock().unwrap().push((id.to_string(), view.version))
+        });
+        store.register(CollectionSpec {
+            name: "tasks".to_string(),
+            snapshot_event: Some("task-list/update".to_string()),
+            list_field: Some("list".to_string()),
+            ..CollectionSpec::default()
+        });
+        store
+            .apply_push("task-list/update", &snapshot(1, &["a"]))
+            .unwrap();
+
+        let subscription = store.subscribe("tasks", StoreFilter::default()).unwrap();
+        assert_eq!(subscription.view.version, 1);
+        assert_eq!(subscription.view.total, 1);
+        // 订阅时不推送
+        assert!(notified.lock().unwrap().is_empty());
+
+        store
+            .apply_push("task-list/update", &snapshot(2, &["a", "b"]))
+            .unwrap();
+        assert_eq!(
+            *notified.lock().unwrap(),
+            vec![(subscription.subscription.clone(), 2)]
+        );
+
+        assert!(matches!(
+            store.subscribe("missing", StoreFilter::default()),
+            Err(StoreError::NotFound(_))
+        ));
+        assert_eq!(store.subscriptions.len(), 1);
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dashmap::DashSet;
+use serde::{Deserialize, Serialize};
+
+use super::{inspector::Inspector, metrics::Metrics, recorder::Recorder, w_client::RecvData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Send,
+    Recv,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// 所有收发的帧都经过这里, 分发给统计, 录制和检查器
+#[derive(Default)]
+pub struct Traffic {
+    pub recorder: Recorder,
+    pub inspector: Inspector,
+    pub metrics: Metrics,
+    // 客户端内部请求的序号, 它们的响应不经过统计, 录制和检查器
+    quiet: DashSet<String>,
+}
+
+impl Traffic {
+    /// `decoded` 为调用方已经解码好的消息, 避免重复解析
+    pub fn observe(
+        &self,
+        direction: Direction,
+        address: &str,
+        payload: &[u8],
+        decoded: &Result<RecvData, String>,
+    ) {
+        if let Ok(RecvData::Response(response)) = decoded {
+            if self.quiet.remove(&response.sequence).is_some() {
+                return;
+            }
+        }
+        self.metrics
+            .observe_frame(direction, address, payload.len(), decoded);
+        if self.recorder.is_recording() {
+            self.recorder.record(direction, address, payload, decoded);
+        }
+        if self.inspector.is_enabled() {
+            self.inspector
+                .record(direction, address, payload.len(), decoded);
+        }
+    }
+
+    /// 标记内部请求, 它的响应不会被观察. 在发送请求之前调用
+    pub fn quiet(&self, sequence: &str) {
+        self.quiet.insert(sequence.to_string());
+    }
+
+    /// 请求结束时取消标记, 没有收到响应时也不会残留
+    pub fn unquiet(&self, sequence: &str) {
+        self.quiet.remove(sequence);
+    }
+}
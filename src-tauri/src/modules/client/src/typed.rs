@@ -0,0 +1,180 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use dashmap::DashMap;
+use log::warn;
+use proto::{
+    message::{Body, Push},
+    service::{RpcFuture, RpcTransport},
+    MessageBody,
+};
+use protobuf::MessageField;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use super::w_client::WClient;
+
+/// 一个请求地址的声明, 请求, 响应和错误的类型只写一次
+pub trait Endpoint {
+    const URL: &'static str;
+    type Request: Serialize;
+    type Response: DeserializeOwned;
+    /// 服务端返回非 OK 状态时 data 的类型
+    type Error: DeserializeOwned;
+}
+
+/// 一个推送事件的声明
+pub trait PushEvent {
+    const EVENT: &'static str;
+    type Data: DeserializeOwned;
+}
+
+/// 声明请求地址: `endpoint!(pub TaskList, "/task/list", TaskQuery => Vec<Task>, String);`,
+/// 省略错误类型时为 `serde_json::Value`
+#[macro_export]
+macro_rules! endpoint {
+    ($(#[$meta:meta])* $vis:vis $name:ident, $url:expr, $request:ty => $response:ty, $error:ty) => {
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::typed::Endpoint for $name {
+            const URL: &'static str = $url;
+            type Request = $request;
+            type Response = $response;
+            type Error = $error;
+        }
+    };
+    ($(#[$meta:meta])* $vis:vis $name:ident, $url:expr, $request:ty => $response:ty) => {
+        $crate::endpoint!($(#[$meta])* $vis $name, $url, $request => $response, serde_json::Value);
+    };
+}
+
+/// 声明推送事件: `push_event!(pub TaskUpdated, "task/update", Task);`
+#[macro_export]
+macro_rules! push_event {
+    ($(#[$meta:meta])* $vis:vis $name:ident, $event:expr, $data:ty) => {
+        $(#[$meta])*
+        $vis struct $name;
+
+        impl $crate::typed::PushEvent for $name {
+            const EVENT: &'static str = $event;
+            type Data = $data;
+        }
+    };
+}
+
+#[derive(Debug, Error)]
+pub enum RequestError<E> {
+    /// 服务端返回了非 OK 的状态, `error` 为解码后的 data
+    #[error("server returned status {status}")]
+    Server { status: i32, error: E },
+    /// 超时或发送失败, 请求不一定到达了服务端
+    #[error("{0}")]
+    Local(String),
+    #[error("encode request error: {0}")]
+    Encode(serde_json::Error),
+    #[error("decode response error: {0}")]
+    Decode(serde_json::Error),
+}
+
+/// 按 `Body::json_value` 解码后再反序列化
+pub fn decode_body<T: DeserializeOwned>(body: &MessageField<Body>) -> serde_json::Result<T> {
+    serde_json::from_value(body.json_value())
+}
+
+impl WClient {
+    /// 按 `Endpoint` 的声明发送请求并解码响应
+    pub async fn request_typed<E: Endpoint>(
+        &mut self,
+        request: &E::Request,
+    ) -> Result<E::Response, RequestError<E::Error>> {
+        let data = serde_json::to_value(request).map_err(RequestError::Encode)?;
+        match self
+            .request_response(E::URL.to_string(), Body::from_serialize(data))
+            .await
+        {
+            Ok(response) => decode_body(&response.data).map_err(RequestError::Decode),
+            Err(response) => match response.status {
+                Some(status) => Err(RequestError::Server {
+                    status: status.value(),
+                    error: decode_body(&response.data).map_err(RequestError::Decode)?,
+                }),
+                None => Err(RequestError::Local(
+                    decode_body(&response.data).unwrap_or_default(),
+                )),
+            },
+        }
+    }
+}
+
+/// proto 中 service 生成的客户端可以直接使用 `WClient` 发送请求:
+/// `TaskServiceClient::new(client).list(&query).await`
+impl RpcTransport for WClient {
+    fn call(&mut self, url: &str, body: Body) -> RpcFuture<'_> {
+        Box::pin(self.request_response(url.to_string(), body))
+    }
+}
+
+type PushHandler = Arc<dyn Fn(&Push) + Send + Sync>;
+
+/// 按事件名注册的推送回调, 在客户端的入站任务中依次调用, 回调中不要阻塞
+#[derive(Default)]
+pub struct PushHandlers {
+    handlers: DashMap<String, Vec<(u64, PushHandler)>>,
+    next_id: AtomicU64,
+}
+
+impl PushHandlers {
+    /// 注册回调, 返回的 id 用于取消
+    pub fn on<P, F>(&self, handler: F) -> u64
+    where
+        P: PushEvent,
+        F: Fn(P::Data) + Send + Sync + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let handler: PushHandler =
+            Arc::new(
+                move |push: &Push| match decode_body::<P::Data>(&push.data) {
+                    Ok(data) => handler(data),
+                    Err(error) => warn!("decode push {} error: {}", P::EVENT, error),
+                },
+            );
+        self.handlers
+            .entry(P::EVENT.to_string())
+            .or_default()
+            .push((id, handler));
+        id
+    }
+
+    pub fn off(&self, id: u64) -> bool {
+        let mut removed = false;
+        for mut handlers in self.handlers.iter_mut() {
+            let len = handlers.len();
+            handlers.retain(|(handler_id, _)| *handler_id != id);
+            removed |= handlers.len() != len;
+        }
+        removed
+    }
+
+    /// 调用该事件的所有回调, 返回调用的数量
+    pub fn dispatch(&self, push: &Push) -> usize {
+        // 先取出回调再调用, 回调中可以注册或取消其他回调
+        let handlers: Vec<PushHandler> = match self.handlers.get(&push.event) {
+            Some(handlers) => handlers
+                .iter()
+                .map(|(_, handler)| handler.clone())
+                .collect(),
+            None => return 0,
+        };
+        for handler in handlers.iter() {
+            handler(push);
+        }
+        handlers.len()
+    }
+
+    pub fn clear(&self) {
+        self.handlers.clear();
+    }
+}
@@ -6,4 +6,6 @@ pub const CLIENT_IDENTIFICATION_REQUEST: &str = "CLIENT_IDENTIFICATION_REQUEST";
 pub const CLIENT_IDENTIFICATION_CLOSE: &str = "CLIENT_IDENTIFICATION_CLOSE";
 pub const CLIENT_IDENTIFICATION_ERROR: &str = "CLIENT_IDENTIFICATION_ERROR";
 pub const CLIENT_IDENTIFICATION_CONNECT_ERROR: &str = "CLIENT_IDENTIFICATION_CONNECT_ERROR";
-
+pub const CLIENT_IDENTIFICATION_INSPECTOR: &str = "CLIENT_IDENTIFICATION_INSPECTOR";
+pub const CLIENT_IDENTIFICATION_STORE: &str = "CLIENT_IDENTIFICATION_STORE";
+pub const CLIENT_IDENTIFICATION_RESYNC: &str = "CLIENT_IDENTIFICATION_RESYNC";
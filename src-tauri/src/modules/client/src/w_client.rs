@@ -0,0 +1,725 @@
+use std::{fmt::Debug, sync::Arc, time::Instant};
+
+use conn::Connection;
+use dashmap::DashMap;
+use promise::{self, Promise, PromiseResult};
+use proto::{
+    descriptors::{pool, FILE_DESCRIPTOR_SET},
+    json::{unknown_json, UNKNOWN_FIELDS},
+    message::{Body, Push, Request, Response, Status as MessageState},
+    now_ms, MessageBody, MessageType, PushPayload, SendTime,
+};
+use protobuf::{Enum, EnumOrUnknown, Message, MessageDyn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::ack::{AckBuffer, Delivery};
+use crate::clock::{ClockSample, ClockSnapshot};
+use crate::emitter::{ClientScope, Emitter};
+use crate::metrics::Outcome;
+use crate::push_policy::PushGate;
+use crate::sequence::ResyncNotice;
+use crate::sink::EventSink;
+use crate::store::{
+    subscription_payload, CollectionSpec, StateStore, StoreAction, StoreError, StoreView,
+};
+use crate::traffic::{Direction, Traffic};
+use crate::typed::PushHandlers;
+use crate::utils::{
+    CLIENT_IDENTIFICATION, CLIENT_IDENTIFICATION_ERROR, CLIENT_IDENTIFICATION_PUSH,
+    CLIENT_IDENTIFICATION_REQUEST, CLIENT_IDENTIFICATION_RESPONSE, CLIENT_IDENTIFICATION_RESYNC,
+    CLIENT_IDENTIFICATION_STORE,
+};
+use log::error;
+
+use crate::{ctx_error, ctx_info, ctx_warn, logger::LogContext, wrap_event_err};
+
+/// 确认推送的地址
+pub const PUSH_ACK_URL: &str = "/push/ack";
+/// 获取服务端 proto 描述的地址
+pub const DESCRIPTORS_URL: &str = "/proto/descriptors";
+/// rust 中订阅推送时每个订阅者最多积压的推送, 超过后丢弃最早的
+pub const PUSH_BROADCAST_CAPACITY: usize = 256;
+/// 拉取完整列表失败后再次拉取的间隔
+const RESYNC_RETRY_DELAY: tokio::time::Duration = tokio::time::Duration::from_secs(2);
+
+#[derive(Clone)]
+pub enum RecvData {
+    Error(String),
+    Push(Push),
+    Request(Request),
+    Response(Response),
+    /// 推送缺号且无法重发, 不是服务端发来的消息
+    Resync(ResyncNotice),
+}
+
+unsafe impl Send for RecvData {}
+unsafe impl Sync for RecvData {}
+
+fn enum_json<E: Enum + Debug>(value: &Option<EnumOrUnknown<E>>) -> Value {
+    match value {
+        Some(value) => match value.enum_value() {
+            Ok(value) => Value::String(format!("{:?}", value)),
+            Err(value) => Value::from(value),
+        },
+        None => Value::Null,
+    }
+}
+
+/// 新版本的服务端可能带上这里不认识的字段, 放在 `@unknown` 中显示
+fn with_unknown(message: &dyn MessageDyn, mut value: Value) -> Value {
+    if let (Some(unknown), Value::Object(object)) = (unknown_json(message), &mut value) {
+        object.insert(UNKNOWN_FIELDS.to_string(), unknown);
+    }
+    value
+}
+
+/// 本地生成的失败响应 (超时, 发送失败), 没有状态码
+fn local_response(sequence: &str, message: String) -> Response {
+    let mut response = Response::new();
+    response.type_ = "response".to_string();
+    response.sequence = sequence.to_string();
+    response.data = Some(Body::from_serialize(Value::String(message))).into();
+    response
+}
+
+impl RecvData {
+    /// 解析一帧数据, 第一个字节为消息类型
+    pub fn decode(payload: &[u8]) -> Result<RecvData, String> {
+        let first_byte = match payload.first() {
+            Some(byte) => *byte,
+            None => return Err("empty payload".to_string()),
+        };
+        let data = &payload[1..];
+        match MessageType::from_u8(first_byte) {
+            MessageType::PUSH => Push::parse_from_bytes(data)
+                .map(RecvData::Push)
+                .map_err(|error| format!("parse push error: {}", error)),
+            MessageType::REQUEST => Request::parse_from_bytes(data)
+                .map(RecvData::Request)
+                .map_err(|error| format!("parse request error: {}", error)),
+            MessageType::RESPONSE => Response::parse_from_bytes(data)
+                .map(RecvData::Response)
+                .map_err(|error| format!("parse response error: {}", error)),
+            MessageType::OTHER => Err(format!("unknown message type: {}", first_byte)),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RecvData::Error(_) => "error",
+            RecvData::Push(_) => "push",
+            RecvData::Request(_) => "request",
+            RecvData::Response(_) => "response",
+            RecvData::Resync(_) => "resync",
+        }
+    }
+
+    /// 解码后的消息, 用于记录和调试
+    pub fn json_value(&self) -> Value {
+        match self {
+            RecvData::Error(error) => Value::String(error.clone()),
+            RecvData::Push(push) => with_unknown(
+                push,
+                json!({
+                    "type": push.type_,
+                    "event": push.event,
+                    "status": enum_json(&push.status),
+                    "sendTimeMs": push.send_time_ms(),
+                    "seq": push.seq,
+                    "session": push.session,
+                    "data": push.data.json_value(),
+                }),
+            ),
+            RecvData::Request(request) => with_unknown(
+                request,
+                json!({
+                    "type": request.type_,
+                    "url": request.url,
+                    "sequence": request.sequence,
+                    "sendTimeMs": request.send_time_ms(),
+                    "data": request.data.json_value(),
+                }),
+            ),
+            RecvData::Response(response) => with_unknown(
+                response,
+                json!({
+                    "type": response.type_,
+                    "sequence": response.sequence,
+                    "status": enum_json(&response.status),
+                    "sendTimeMs": response.send_time_ms(),
+                    "data": response.data.json_value(),
+                }),
+            ),
+            RecvData::Resync(notice) => serde_json::to_value(notice).unwrap_or_default(),
+        }
+    }
+}
+
+/// 发起连接的一方
+#[derive(Debug, Clone, Default)]
+pub struct ClientOwner {
+    pub window: String,
+    /// 页面每次加载时生成的标识, 同一个客户端再次 connect 时标识变化说明页面重新加载了
+    pub page: Option<String>,
+}
+
+impl ClientOwner {
+    pub fn new(window: &str) -> Self {
+        Self {
+            window: window.to_string(),
+            page: None,
+        }
+    }
+
+    pub fn page(mut self, page: Option<String>) -> Self {
+        self.page = page;
+        self
+    }
+}
+
+/// 客户端的身份, 同一个窗口可以用不同的名称对同一个地址建立多个客户端
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientKey {
+    pub window: String,
+    pub address: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    pub id: String,
+    pub window: String,
+    pub address: String,
+    pub name: Option<String>,
+    pub scope: ClientScope,
+    /// 接收事件的窗口, app 作用域时为空
+    pub windows: Option<Vec<String>>,
+    /// 还在等待响应的请求数
+    pub pending_requests: usize,
+    /// 还没有被页面确认的推送数
+    pub pending_acks: usize,
+}
+
+pub struct WClient {
+    pub ip: String,
+    pub port: u16,
+    pub address: String,
+    pub client_id: String,
+    pub name: Option<String>,
+    // 创建客户端的窗口
+    pub window: String,
+    // 事件按作用域发往一个或多个窗口
+    pub emitter: Arc<Emitter>,
+    pub conn: Connection,
+    pub sequences: Arc<DashMap<String, Promise<Response>>>,
+    pub traffic: Arc<Traffic>,
+    pub pushes: Arc<PushGate>,
+    pub store: Arc<StateStore>,
+    pub acks: Arc<AckBuffer>,
+    // 按顺序去重后的推送, 不经过推送策略
+    pub push_tx: broadcast::Sender<Push>,
+    // rust 中按事件注册的推送回调
+    pub handlers: Arc<PushHandlers>,
+    // 回放用的客户端, 不向服务端发送任何数据
+    offline: bool,
+}
+
+unsafe impl Send for WClient {}
+unsafe impl Sync for WClient {}
+
+impl Clone for WClient {
+    fn clone(&self) -> Self {
+        Self {
+            client_id: self.client_id.clone(),
+            name: self.name.clone(),
+            window: self.window.clone(),
+            emitter: self.emitter.clone(),
+            conn: self.conn.clone(),
+            address: self.address.clone(),
+            sequences: self.sequences.clone(),
+            ip: self.ip.clone(),
+            port: self.port,
+            traffic: self.traffic.clone(),
+            pushes: self.pushes.clone(),
+            store: self.store.clone(),
+            acks: self.acks.clone(),
+            push_tx: self.push_tx.clone(),
+            handlers: self.handlers.clone(),
+            offline: self.offline,
+        }
+    }
+}
+
+impl WClient {
+    /// 创建客户端的窗口为 emitter 的 owner
+    pub fn build(
+        emitter: Emitter,
+        ip: String,
+        port: u16,
+        name: Option<String>,
+        conn: Connection,
+        traffic: Arc<Traffic>,
+    ) -> Self {
+        let emitter = Arc::new(emitter);
+        let push_emitter = emitter.clone();
+        let pushes = PushGate::new(move |push: &Push| {
+            wrap_event_err!(
+                push_emitter,
+                CLIENT_IDENTIFICATION_PUSH,
+                PushPayload::from(push)
+            );
+        });
+        let store_emitter = emitter.clone();
+        let store = StateStore::new(move |subscription: &str, view: &StoreView| {
+            wrap_event_err!(
+                store_emitter,
+                CLIENT_IDENTIFICATION_STORE,
+                subscription_payload(subscription, view)
+            );
+        });
+        Self {
+            client_id: Uuid::new_v4().to_string(),
+            name,
+            window: emitter.owner().to_string(),
+            emitter,
+            ip: ip.clone(),
+            port: port,
+            address: format!("{}:{}", ip, port),
+            conn,
+            sequences: Arc::new(DashMap::new()),
+            traffic,
+            pushes: Arc::new(pushes),
+            store: Arc::new(store),
+            acks: Arc::new(AckBuffer::default()),
+            push_tx: broadcast::channel(PUSH_BROADCAST_CAPACITY).0,
+            handlers: Arc::new(PushHandlers::default()),
+            offline: false,
+        }
+    }
+
+    /// 同一地址的离线副本, 用于回放: 事件发往 `sink`, 集合副本, 确认和统计都是独立的,
+    /// 请求直接失败而不会发往服务端. 集合的配置从当前客户端复制
+    pub fn offline_copy(&self, sink: Arc<dyn EventSink>) -> Self {
+        let emitter = Emitter::new(sink, &self.window, ClientScope::Window);
+        let mut client = Self::build(
+            emitter,
+            self.ip.clone(),
+            self.port,
+            self.name.clone(),
+            self.conn.clone(),
+            Arc::new(Traffic::default()),
+        );
+        client.offline = true;
+        for spec in self.store.specs() {
+            client.store.register(spec);
+        }
+        client
+    }
+
+    pub fn key(&self) -> ClientKey {
+        ClientKey {
+            window: self.window.clone(),
+            address: self.address.clone(),
+            name: self.name.clone(),
+        }
+    }
+
+    pub fn info(&self) -> ClientInfo {
+        ClientInfo {
+            id: self.client_id.clone(),
+            window: self.window.clone(),
+            address: self.address.clone(),
+            name: self.name.clone(),
+            scope: self.emitter.scope(),
+            windows: self.emitter.windows(),
+            pending_requests: self.sequences.len(),
+            pending_acks: self.acks.len(),
+        }
+    }
+
+    pub fn log_ctx(&self) -> LogContext {
+        LogContext::new()
+            .address(&self.address)
+            .client_id(&self.client_id)
+            .window(&self.window)
+    }
+
+    pub async fn request(&mut self, url: String, data: Body) -> Result<Value, Value> {
+        self.request_response(url, data)
+            .await
+            .map(|response| response.data.json_value())
+            .map_err(|response| response.data.json_value())
+    }
+
+    /// 返回完整的响应, 本地失败 (超时, 发送失败) 的响应没有状态码
+    pub async fn request_response(
+        &mut self,
+        url: String,
+        data: Body,
+    ) -> Result<Response, Response> {
+        self.send_request(url, data, false).await
+    }
+
+    /// 客户端自己发出的请求, 不发出请求事件, 也不经过统计, 录制和检查器
+    async fn quiet_request(&mut self, url: String, data: Body) -> Result<Response, Response> {
+        self.send_request(url, data, true).await
+    }
+
+    async fn send_request(
+        &mut self,
+        url: String,
+        data: Body,
+        quiet: bool,
+    ) -> Result<Response, Response> {
+        let mut promise = Promise::<Response>::new();
+        let sequence = Uuid::new_v4().to_string();
+        if self.offline {
+            return Err(local_response(&sequence, "offline".to_string()));
+        }
+        let start = Instant::now();
+
+        let mut request = Request::new();
+
+        request.sequence = sequence.clone();
+        request.type_ = "request".to_string();
+        request.url = url.clone();
+        request.data = Some(data).into();
+        let sent = now_ms();
+        request.sendTimeMs = sent;
+        // 旧版本的服务端只读 float 的 sendTime
+        request.sendTime = sent as f32;
+
+        // 在request 序列化数据前加上请求标识
+        match request.write_to_bytes() {
+            Ok(mut data) => {
+                data.insert(0, MessageType::REQUEST.into());
+                let mut send_error = false;
+                if quiet {
+                    self.traffic.quiet(&sequence);
+                }
+                match self.conn.send(&data).await {
+                    Ok(_) if quiet => {}
+                    Ok(_) => {
+                        self.traffic.observe(
+                            Direction::Send,
+                            &self.address,
+                            &data,
+                            &Ok(RecvData::Request(request)),
+                        );
+                        wrap_event_err!(self.emitter, CLIENT_IDENTIFICATION_REQUEST, "success");
+                    }
+                    Err(error) => {
+                        ctx_error!(
+                            self.log_ctx().sequence(&sequence).url(&url),
+                            "request error: {:?}",
+                            error
+                        );
+                        send_error = true;
+                        self.sequences.remove(&sequence);
+                        promise
+                            .reject(local_response(
+                                &sequence,
+                                format!("request error: {:?}", error),
+                            ))
+                            .await
+                            .unwrap();
+                    }
+                }
+                self.sequences.insert(sequence.clone(), promise.clone());
+
+                let mut t_promise = promise.clone();
+                let t_sequence = sequence.clone();
+                let handle = tokio::spawn(async move {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+                    t_promise
+                        .reject(local_response(&t_sequence, "timeout".to_string()))
+                        .await
+                        .unwrap();
+                });
+
+                let (res, outcome) = match promise.await {
+                    PromiseResult::Resolved(response) => (Ok(response), Outcome::Ok),
+                    PromiseResult::Rejected(response) => {
+                        let outcome = match response.status {
+                            Some(status) => Outcome::Status(status.value()),
+                            None if send_error => Outcome::SendError,
+                            None => Outcome::Timeout,
+                        };
+                        (Err(response), outcome)
+                    }
+                };
+                // 清除定时器
+                handle.abort();
+                self.sequences.remove(&sequence);
+                self.observe_clock(sent, &res);
+                if quiet {
+                    self.traffic.unquiet(&sequence);
+                } else {
+                    self.traffic.metrics.observe_request(
+                        &self.address,
+                        &url,
+                        start.elapsed(),
+                        outcome,
+                    );
+                }
+                res
+            }
+            Err(_) => Err(local_response(&sequence, "data error".to_string())),
+        }
+    }
+
+    /// 服务端返回了毫秒时间时更新时钟偏差的估算, 本地失败的响应不参与
+    fn observe_clock(&self, sent: i64, res: &Result<Response, Response>) {
+        let response = match res {
+            Ok(response) | Err(response) => response,
+        };
+        if response.status.is_none() || response.sendTimeMs == 0 {
+            return;
+        }
+        let received = if response.receiveTimeMs != 0 {
+            response.receiveTimeMs
+        } else {
+            response.sendTimeMs
+        };
+        self.traffic.metrics.observe_clock(
+            &self.address,
+            ClockSample::new(sent, received, response.sendTimeMs, now_ms()),
+        );
+    }
+
+    /// 当前连接估算的服务端时钟偏差, 还没有样本时为空
+    pub fn clock(&self) -> Option<ClockSnapshot> {
+        self.traffic.metrics.clock(&self.address)
+    }
+
+    pub async fn handle_response(&mut self, response: &Response) {
+        wrap_event_err!(self.emitter, CLIENT_IDENTIFICATION_RESPONSE, "response");
+        let sequence = response.sequence.clone();
+        if let Some(promise) = self.sequences.remove(&sequence) {
+            let mut promise = promise.1;
+            match response.status {
+                Some(status) => {
+                    if status != MessageState::OK.into() {
+                        promise.reject(response.clone()).await.unwrap();
+                        return;
+                    }
+                    promise.resolve(response.clone()).await.unwrap();
+                }
+                None => {
+                    ctx_warn!(
+                        self.log_ctx().sequence(&sequence),
+                        "response status is none"
+                    )
+                }
+            }
+        }
+    }
+
+    /// push 先更新集合副本, 再按事件配置的策略合并后发往页面.
+    /// 需要确认的推送每条都会发送, 并保存到页面确认为止
+    pub fn handle_push(&mut self, data: &Push) {
+        let needs_ack = data.ack && !data.id.is_empty();
+        if needs_ack {
+            match self.acks.receive(data) {
+                (Delivery::Deliver, Some(evicted)) => ctx_warn!(
+                    self.log_ctx(),
+                    "pending ack buffer full, drop push {}",
+                    evicted.id
+                ),
+                (Delivery::Deliver, None) => {}
+                (Delivery::Pending, _) => return,
+                (Delivery::Acked, _) => {
+                    // 服务端没有收到确认, 再确认一次
+                    self.send_ack(vec![data.id.clone()]);
+                    return;
+                }
+            }
+        }
+        match self.store.apply_push(&data.event, &data.data.json_value()) {
+            Ok(StoreAction::Resync(spec)) => self.resync(spec),
+            Ok(_) => {}
+            Err(error) => ctx_warn!(self.log_ctx(), "apply {} error: {}", data.event, error),
+        }
+        // 没有订阅者时发送失败, 直接忽略
+        let _ = self.push_tx.send(data.clone());
+        self.handlers.dispatch(data);
+        if needs_ack {
+            wrap_event_err!(
+                self.emitter,
+                CLIENT_IDENTIFICATION_PUSH,
+                PushPayload::from(data)
+            );
+        } else {
+            self.pushes.push(data.clone());
+        }
+    }
+
+    /// 在 rust 中订阅推送, 每条推送只会收到一次
+    pub fn subscribe_pushes(&self) -> broadcast::Receiver<Push> {
+        self.push_tx.subscribe()
+    }
+
+    /// 页面处理完推送后确认, 返回确认成功的数量
+    pub fn ack(&self, ids: &[String]) -> usize {
+        let acked = self.acks.ack(ids);
+        let count = acked.len();
+        if count > 0 {
+            self.send_ack(acked);
+        }
+        count
+    }
+
+    fn send_ack(&self, ids: Vec<String>) {
+        let mut this = self.clone();
+        tokio::spawn(async move {
+            // 确认失败时服务端会重发, 重发的推送按 id 去重
+            if let Err(error) = this
+                .request(
+                    PUSH_ACK_URL.to_string(),
+                    Body::from_serialize(json!({ "ids": ids })),
+                )
+                .await
+            {
+                ctx_warn!(this.log_ctx().url(PUSH_ACK_URL), "ack error: {}", error);
+            }
+        });
+    }
+
+    /// 重新发送所有未确认的推送, 页面重新加载后调用
+    pub fn redeliver(&self) -> usize {
+        let pending = self.acks.pending();
+        for push in pending.iter() {
+            wrap_event_err!(
+                self.emitter,
+                CLIENT_IDENTIFICATION_PUSH,
+                PushPayload::from(push)
+            );
+        }
+        pending.len()
+    }
+
+    /// 拒绝所有还在等待响应的请求
+    pub async fn reject_pending(&self, reason: &str) {
+        let pending: Vec<(String, Promise<Response>)> = self
+            .sequences
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        for (sequence, mut promise) in pending {
+            self.sequences.remove(&sequence);
+            let _ = promise
+                .reject(local_response(&sequence, reason.to_string()))
+                .await;
+        }
+    }
+
+    /// 页面重新加载后旧页面的订阅, 推送策略和请求都失效了
+    pub fn reset(&self, reason: &str) {
+        self.store.clear_subscriptions();
+        self.pushes.reset();
+        let this = self.clone();
+        let reason = reason.to_string();
+        tokio::spawn(async move {
+            this.reject_pending(&reason).await;
+        });
+    }
+
+    /// 推送缺失且无法重发, 通知页面并重新拉取所有集合
+    pub fn handle_resync(&mut self, notice: &ResyncNotice) {
+        ctx_warn!(
+            self.log_ctx(),
+            "push resync after {}#{}: {}",
+            notice.session,
+            notice.after,
+            notice.reason
+        );
+        for spec in self.store.specs() {
+            if let Ok(StoreAction::Resync(spec)) = self.store.request_resync(&spec.name) {
+                self.resync(spec);
+            }
+        }
+        wrap_event_err!(self.emitter, CLIENT_IDENTIFICATION_RESYNC, notice);
+    }
+
+    /// 请求完整列表替换集合副本
+    pub fn resync(&self, spec: CollectionSpec) {
+        let url = match spec.resync_url.clone() {
+            Some(url) => url,
+            None => return,
+        };
+        let mut this = self.clone();
+        tokio::spawn(async move {
+            loop {
+                ctx_info!(this.log_ctx().url(&url), "resync {}", spec.name);
+                let res = this
+                    .request(url.clone(), Body::from_serialize(spec.resync_data.clone()))
+                    .await;
+                let error = match this.store.resync_done(&spec.name, res) {
+                    Ok(_) => return,
+                    Err(error) => error,
+                };
+                ctx_warn!(
+                    this.log_ctx().url(&url),
+                    "resync {} error: {}",
+                    spec.name,
+                    error
+                );
+                match error {
+                    StoreError::ResyncFailed { retry: true, .. } => {
+                        tokio::time::sleep(RESYNC_RETRY_DELAY).await
+                    }
+                    _ => return,
+                }
+            }
+        });
+    }
+
+    pub fn handle_error(&mut self, data: &str) {
+        wrap_event_err!(self.emitter, CLIENT_IDENTIFICATION_ERROR, data)
+    }
+
+    /// 请求服务端的 FileDescriptorSet 并加载, 返回其中的消息类型.
+    /// 响应的 payload 为 FileDescriptorSet, 或者 data 为它的 base64 编码
+    pub async fn fetch_descriptors(&mut self) -> Result<Vec<String>, String> {
+        let response = self
+            .quiet_request(DESCRIPTORS_URL.to_string(), Body::from_serialize(json!({})))
+            .await
+            .map_err(|response| response.data.json_value().to_string())?;
+        let body = response.data.into_option().unwrap_or_default();
+        let set = if body.messageType == FILE_DESCRIPTOR_SET {
+            body.payload
+        } else {
+            match body.json_value() {
+                Value::String(set) => base64::decode(&set).map_err(|error| error.to_string())?,
+                data => return Err(format!("unexpected descriptors response: {}", data)),
+            }
+        };
+        pool().load_set(&set).map_err(|error| error.to_string())
+    }
+
+    /// 由客户端的入站任务依次调用, 处理完一条再处理下一条以保证顺序
+    pub async fn handle_message(&mut self, data: &RecvData) {
+        match data {
+            RecvData::Push(data) => {
+                self.handle_push(data);
+            }
+            RecvData::Response(data) => {
+                self.handle_response(data).await;
+            }
+            RecvData::Error(data) => {
+                self.handle_error(data);
+            }
+            RecvData::Resync(notice) => {
+                self.handle_resync(notice);
+            }
+            data => {
+                ctx_info!(self.log_ctx(), "ignore {} message", data.kind());
+            }
+        }
+    }
+}
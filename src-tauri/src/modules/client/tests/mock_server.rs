@@ -0,0 +1,362 @@
+//! 用 mock 服务端测试客户端的请求, 超时, 推送, 重连和确认
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use client::{
+    client_manage::{ClientManage, ConnState},
+    emitter::ClientScope,
+    inspector::InspectorFilter,
+    recorder::TrafficRecord,
+    replay,
+    sink::{ChannelSink, SinkEvent},
+    traffic::Direction,
+    utils::{CLIENT_IDENTIFICATION_PUSH, CLIENT_IDENTIFICATION_REQUEST},
+    w_client::{ClientOwner, RecvData, WClient},
+};
+use mock_server::{frame, Fault, MockHandle, MockServer, Reply, Script};
+use proto::{
+    message::{Body, Push},
+    MessageBody, MessageType,
+};
+use serde_json::{json, Value};
+use tokio::{
+    sync::{broadcast, mpsc::UnboundedReceiver},
+    time::timeout,
+};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+struct Harness {
+    server: MockHandle,
+    manage: ClientManage,
+    client: WClient,
+    _events: UnboundedReceiver<SinkEvent>,
+}
+
+async fn connect(script: Script) -> Harness {
+    let server = MockServer::new(script).bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr();
+    let manage = ClientManage::new();
+    let (sink, events) = ChannelSink::new();
+    let client_id = manage
+        .add_client(
+            Arc::new(sink),
+            ClientOwner::new("test"),
+            addr.ip().to_string(),
+            addr.port(),
+            None,
+            ClientScope::Window,
+        )
+        .await
+        .unwrap();
+    let client = manage.get_client(client_id).unwrap();
+    Harness {
+        server,
+        manage,
+        client,
+        _events: events,
+    }
+}
+
+async fn next_push(pushes: &mut broadcast::Receiver<Push>) -> Push {
+    timeout(WAIT, pushes.recv()).await.unwrap().unwrap()
+}
+
+/// 条件满足之前一直重试, 超过 `WAIT` 时失败
+async fn eventually<F, Fut>(mut check: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    timeout(WAIT, async {
+        while !check().await {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+fn body(data: Value) -> Body {
+    Body::from_serialize(data)
+}
+
+#[tokio::test]
+async fn request_response() {
+    let mut h = connect(
+        Script::default()
+            .route("/task/list", Reply::ok(json!([{ "id": 1 }])))
+            .route("/task/fail", Reply::error(500, json!("boom"))),
+    )
+    .await;
+
+    let list = h.client.request("/task/list".into(), body(json!({}))).await;
+    assert_eq!(list, Ok(json!([{ "id": 1 }])));
+
+    let fail = h.client.request("/task/fail".into(), body(json!({}))).await;
+    assert_eq!(fail, Err(json!("boom")));
+
+    let metrics = h.manage.metrics().remove(&h.client.address).unwrap();
+    assert_eq!(metrics.urls["/task/list"].ok, 1);
+    assert_eq!(metrics.urls["/task/fail"].statuses[&500], 1);
+    h.server.shutdown();
+}
+
+#[tokio::test]
+async fn request_timeout() {
+    let mut h =
+        connect(Script::default().route("/task/slow", Reply::ok(Value::Null).fault(Fault::Drop)))
+            .await;
+
+    let res = h.client.request("/task/slow".into(), body(json!({}))).await;
+    assert_eq!(res, Err(json!("timeout")));
+    assert_eq!(h.client.info().pending_requests, 0);
+
+    let metrics = h.manage.metrics().remove(&h.client.address).unwrap();
+    assert_eq!(metrics.urls["/task/slow"].timeouts, 1);
+    h.server.shutdown();
+}
+
+#[tokio::test]
+async fn push_in_order() {
+    let h = connect(Script::default()).await;
+    let mut pushes = h.client.subscribe_pushes();
+
+    for id in 1..=3 {
+        h.server.push("task/update", 0, json!({ "id": id }));
+    }
+    for id in 1..=3 {
+        let push = next_push(&mut pushes).await;
+        assert_eq!(push.event, "task/update");
+        assert_eq!(push.seq, id);
+        assert_eq!(push.data.json_value(), json!({ "id": id }));
+    }
+    h.server.shutdown();
+}
+
+#[tokio::test]
+async fn reconnect_after_disconnect() {
+    let mut h = connect(Script::default().route("/ping", Reply::ok(json!("pong")))).await;
+    let mut state = h.manage.conn_state(&h.client.client_id).unwrap();
+    let mut pushes = h.client.subscribe_pushes();
+    assert_eq!(*state.borrow(), ConnState::Connected);
+
+    h.server.disconnect_all();
+    timeout(
+        WAIT,
+        state.wait_for(|state| *state == ConnState::Reconnecting),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    // 重连之前发送的请求会失败或者一直等到超时, 重连之后请求和推送恢复
+    let client = h.client.clone();
+    eventually(|| {
+        let mut client = client.clone();
+        async move {
+            let ping = client.request("/ping".into(), body(json!({})));
+            matches!(timeout(Duration::from_millis(500), ping).await, Ok(Ok(pong)) if pong == json!("pong"))
+        }
+    })
+    .await;
+    assert_eq!(*state.borrow(), ConnState::Connected);
+
+    h.server.push("task/update", 0, json!({ "id": 1 }));
+    let push = next_push(&mut pushes).await;
+    assert_eq!(push.data.json_value(), json!({ "id": 1 }));
+    h.client
+        .request("/ping".into(), body(json!({})))
+        .await
+        .unwrap();
+    h.server.shutdown();
+}
+
+#[tokio::test]
+async fn ack_reliable_push() {
+    let h = connect(Script::default()).await;
+    let mut pushes = h.client.subscribe_pushes();
+
+    h.server.push_reliable("task/done", 0, json!({ "id": 7 }));
+    let push = next_push(&mut pushes).await;
+    assert!(push.ack);
+    assert!(!push.id.is_empty());
+    assert_eq!(h.server.unacked(), 1);
+    assert_eq!(h.client.info().pending_acks, 1);
+
+    assert_eq!(h.client.ack(std::slice::from_ref(&push.id)), 1);
+    assert_eq!(h.client.info().pending_acks, 0);
+    let server = &h.server;
+    eventually(|| async move { server.unacked() == 0 }).await;
+    // 重复确认不会再发送
+    assert_eq!(h.client.ack(&[push.id]), 0);
+    h.server.shutdown();
+}
+
+#[tokio::test]
+async fn fetch_descriptors_is_quiet() {
+    let mut h = connect(Script::default()).await;
+    h.manage.inspector_configure(Some(true), None);
+    h.manage.metrics_reset();
+    while h._events.try_recv().is_ok() {}
+
+    let _ = h.client.fetch_descriptors().await;
+    assert!(h
+        .manage
+        .inspector_query(&InspectorFilter::default())
+        .is_empty());
+    let metrics = h.manage.metrics();
+    assert!(metrics
+        .values()
+        .all(|conn| conn.frames_out == 0 && conn.frames_in == 0 && conn.urls.is_empty()));
+    while let Ok(event) = h._events.try_recv() {
+        assert!(!event.event.ends_with(CLIENT_IDENTIFICATION_REQUEST));
+    }
+
+    // 普通请求仍然会被记录
+    let _ = h.client.request("/echo".to_string(), body(json!({}))).await;
+    assert_eq!(
+        h.manage.inspector_query(&InspectorFilter::default()).len(),
+        2
+    );
+    h.server.shutdown();
+}
+
+fn recorded_push(address: &str, id: &str) -> TrafficRecord {
+    let mut push = frame::push("task/done", 0, json!({ "id": id }));
+    push.id = id.to_string();
+    push.ack = true;
+    let payload = frame::encode(MessageType::PUSH, &push);
+    TrafficRecord::new(
+        Direction::Recv,
+        address,
+        &payload,
+        &RecvData::decode(&payload),
+    )
+}
+
+#[tokio::test]
+async fn replay_offline() {
+    let mut h = connect(Script::default()).await;
+    let records = vec![
+        recorded_push(&h.client.address, "a"),
+        recorded_push("127.0.0.1:1", "b"),
+    ];
+    let result = replay::replay(&h.client, records, false).await.unwrap();
+    // 只回放客户端地址的帧
+    assert_eq!(result.frames, 1);
+    let pushes: Vec<_> = result
+        .events
+        .iter()
+        .filter(|event| event.event.ends_with(CLIENT_IDENTIFICATION_PUSH))
+        .collect();
+    assert_eq!(pushes.len(), 1);
+    assert_eq!(pushes[0].payload["id"], "a");
+    // 原客户端和页面都不受影响
+    assert_eq!(h.client.info().pending_acks, 0);
+    assert!(h._events.try_recv().is_err());
+    h.server.shutdown();
+}
+
+async fn add(manage: &ClientManage, addr: std::net::SocketAddr, owner: &str) -> String {
+    let (sink, _) = ChannelSink::new();
+    manage
+        .add_client(
+            Arc::new(sink),
+            ClientOwner::new(owner),
+            addr.ip().to_string(),
+            addr.port(),
+            None,
+            ClientScope::Window,
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn concurrent_connects_share_one_client() {
+    let server = MockServer::new(Script::default())
+        .bind("127.0.0.1:0")
+        .await
+        .unwrap();
+    let addr = server.local_addr();
+    let manage = Arc::new(ClientManage::new());
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let manage = manage.clone();
+            tokio::spawn(async move {
+                let id = add(&manage, addr, "test").await;
+                // 返回 id 时客户端已经可以使用
+                assert!(manage.get_client(id.clone()).is_some());
+                id
+            })
+        })
+        .collect();
+    let mut ids = vec![];
+    for task in tasks {
+        ids.push(task.await.unwrap());
+    }
+    ids.dedup();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(manage.all_clients().len(), 1);
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn connect_while_last_client_disconnects() {
+    let server = MockServer::new(Script::default().route("/ping", Reply::ok(json!("pong"))))
+        .bind("127.0.0.1:0")
+        .await
+        .unwrap();
+    let addr = server.local_addr();
+    let manage = Arc::new(ClientManage::new());
+    for round in 0..10 {
+        let first = add(&manage, addr, "first").await;
+        let closing = manage.clone();
+        let removed = tokio::spawn(async move { closing.remove_client(first) });
+        let second = add(&manage, addr, &format!("second-{}", round)).await;
+        removed.await.unwrap().unwrap();
+
+        // 新客户端不能挂在已经关闭的连接上
+        let client = manage.get_client(second.clone()).unwrap();
+        let state = manage.conn_state(&second).unwrap();
+        assert_ne!(*state.borrow(), ConnState::Closed);
+        let res = client
+            .clone()
+            .request("/ping".into(), body(json!({})))
+            .await;
+        assert_eq!(res, Ok(json!("pong")));
+        manage.remove_client(second).unwrap();
+    }
+    server.shutdown();
+}
+
+#[tokio::test]
+async fn remove_window_releases_owned_clients() {
+    let server = MockServer::new(Script::default())
+        .bind("127.0.0.1:0")
+        .await
+        .unwrap();
+    let addr = server.local_addr();
+    let manage = ClientManage::new();
+    let client_id = add(&manage, addr, "w1").await;
+    let app_id = manage
+        .add_client(
+            Arc::new(ChannelSink::new().0),
+            ClientOwner::new("w1"),
+            addr.ip().to_string(),
+            addr.port(),
+            Some("app".to_string()),
+            ClientScope::App,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(manage.remove_window("w2"), 0);
+    assert!(manage.get_client(client_id.clone()).is_some());
+    // app 作用域的客户端不属于任何窗口
+    assert_eq!(manage.remove_window("w1"), 1);
+    assert!(manage.get_client(client_id).is_none());
+    assert!(manage.get_client(app_id).is_some());
+    server.shutdown();
+}
@@ -0,0 +1,49 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use proto::{
+    message::{Body, Push, Response},
+    now_ms, MessageBody, MessageType,
+};
+use protobuf::{EnumOrUnknown, Message};
+use serde_json::Value;
+
+use crate::script::Reply;
+
+/// 与 python 服务端保持一致, 推送和响应的 sendTime 使用秒, 同时带上毫秒的 sendTimeMs
+pub fn now_secs() -> f32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f32()
+}
+
+/// 在序列化数据前加上消息类型标识
+pub fn encode<M: Message>(message_type: MessageType, message: &M) -> Vec<u8> {
+    let mut data = message.write_to_bytes().unwrap_or_default();
+    data.insert(0, message_type.into());
+    data
+}
+
+/// `received` 是收到请求时的 unix 毫秒, 客户端用它估算时钟偏差
+pub fn response(sequence: &str, received: i64, reply: &Reply) -> Response {
+    let mut response = Response::new();
+    response.type_ = "response".to_string();
+    response.sequence = sequence.to_string();
+    response.status = Some(EnumOrUnknown::from_i32(reply.status));
+    response.sendTime = now_secs();
+    response.sendTimeMs = now_ms();
+    response.receiveTimeMs = received;
+    response.data = Some(Body::from_serialize(reply.data.clone())).into();
+    response
+}
+
+pub fn push(event: &str, status: i32, data: Value) -> Push {
+    let mut push = Push::new();
+    push.type_ = "push".to_string();
+    push.event = event.to_string();
+    push.status = Some(EnumOrUnknown::from_i32(status));
+    push.sendTime = now_secs();
+    push.sendTimeMs = now_ms();
+    push.data = Some(Body::from_serialize(data)).into();
+    push
+}
@@ -0,0 +1,9 @@
+//! 模拟 python 任务服务端的 websocket 服务, 使用与客户端相同的分帧和 `message.proto`,
+//! 用于本地开发前端以及测试 rust 客户端.
+
+pub mod frame;
+pub mod script;
+pub mod server;
+
+pub use script::{Fault, Reply, Route, ScheduledPush, Script};
+pub use server::{MockHandle, MockServer};
@@ -0,0 +1,45 @@
+use anyhow::{anyhow, Result};
+use log::info;
+use mock_server::{MockServer, Script};
+
+const DEFAULT_SCRIPT: &str = include_str!("../scripts/default.json");
+
+fn usage() -> String {
+    "usage: mock_server [--host 127.0.0.1] [--port 9673] [script.json]".to_string()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut host = "127.0.0.1".to_string();
+    let mut port = 9673u16;
+    let mut script = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--host" => host = args.next().ok_or_else(|| anyhow!(usage()))?,
+            "--port" => port = args.next().ok_or_else(|| anyhow!(usage()))?.parse()?,
+            "-h" | "--help" => {
+                println!("{}", usage());
+                return Ok(());
+            }
+            path => script = Some(Script::from_file(path)?),
+        }
+    }
+
+    let script = match script {
+        Some(script) => script,
+        None => serde_json::from_str(DEFAULT_SCRIPT)?,
+    };
+
+    let handle = MockServer::new(script)
+        .bind(&format!("{}:{}", host, port))
+        .await?;
+    info!("mock server listening on ws://{}", handle.local_addr());
+
+    tokio::signal::ctrl_c().await?;
+    handle.shutdown();
+    Ok(())
+}
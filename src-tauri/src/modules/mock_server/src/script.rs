@@ -0,0 +1,185 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// 对一次请求的处理方式
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Fault {
+    /// 正常回复
+    #[default]
+    None,
+    /// 收到请求但不回复, 用来触发客户端超时
+    Drop,
+    /// 收到请求后直接断开连接
+    Disconnect,
+}
+
+/// 单个脚本化回复
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Reply {
+    /// `message.Status` 的数值, 200 以外的值会让客户端 reject
+    pub status: i32,
+    pub data: Value,
+    pub delay_ms: u64,
+    pub fault: Fault,
+}
+
+impl Default for Reply {
+    fn default() -> Self {
+        Self {
+            status: 200,
+            data: Value::Null,
+            delay_ms: 0,
+            fault: Fault::None,
+        }
+    }
+}
+
+impl Reply {
+    pub fn ok(data: Value) -> Self {
+        Self::default().data(data)
+    }
+
+    pub fn error(status: i32, data: Value) -> Self {
+        Self::default().status(status).data(data)
+    }
+
+    pub fn status(mut self, status: i32) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn delay(mut self, delay_ms: u64) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    pub fn fault(mut self, fault: Fault) -> Self {
+        self.fault = fault;
+        self
+    }
+}
+
+/// 按 `Request.url` 匹配的一组回复, 依次使用, 用完后重复最后一个
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+    pub url: String,
+    pub replies: Vec<Reply>,
+}
+
+/// 定时推送的 `Push` 事件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledPush {
+    pub event: String,
+    /// `message.InfoType` 的数值
+    #[serde(default)]
+    pub status: i32,
+    #[serde(default)]
+    pub data: Value,
+    /// 连接建立后多久发送第一次
+    #[serde(default)]
+    pub after_ms: u64,
+    /// 重复间隔, 不设置则只发送一次
+    #[serde(default)]
+    pub every_ms: Option<u64>,
+    /// 最多发送次数, 不设置则一直发送
+    #[serde(default)]
+    pub times: Option<u32>,
+    /// 需要客户端确认, 没有确认的推送在下次连接时重发
+    #[serde(default)]
+    pub ack: bool,
+}
+
+impl ScheduledPush {
+    pub fn new(event: &str, data: Value) -> Self {
+        Self {
+            event: event.to_string(),
+            status: 0,
+            data,
+            after_ms: 0,
+            every_ms: None,
+            times: None,
+            ack: false,
+        }
+    }
+
+    pub fn status(mut self, status: i32) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn after(mut self, after_ms: u64) -> Self {
+        self.after_ms = after_ms;
+        self
+    }
+
+    pub fn every(mut self, every_ms: u64) -> Self {
+        self.every_ms = Some(every_ms);
+        self
+    }
+
+    pub fn times(mut self, times: u32) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    pub fn ack(mut self) -> Self {
+        self.ack = true;
+        self
+    }
+}
+
+/// mock 服务的完整脚本, 可以从 json 文件加载, 也可以在代码里构建
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Script {
+    pub routes: Vec<Route>,
+    pub pushes: Vec<ScheduledPush>,
+    /// 没有匹配到路由时的回复
+    pub fallback: Option<Reply>,
+}
+
+impl Script {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn route(mut self, url: &str, reply: Reply) -> Self {
+        match self.routes.iter_mut().find(|route| route.url == url) {
+            Some(route) => route.replies.push(reply),
+            None => self.routes.push(Route {
+                url: url.to_string(),
+                replies: vec![reply],
+            }),
+        }
+        self
+    }
+
+    pub fn push(mut self, push: ScheduledPush) -> Self {
+        self.pushes.push(push);
+        self
+    }
+
+    pub fn fallback(mut self, reply: Reply) -> Self {
+        self.fallback = Some(reply);
+        self
+    }
+
+    pub(crate) fn replies(&self) -> HashMap<String, Vec<Reply>> {
+        self.routes
+            .iter()
+            .map(|route| (route.url.clone(), route.replies.clone()))
+            .collect()
+    }
+}
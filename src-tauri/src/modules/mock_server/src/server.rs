@@ -0,0 +1,490 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use log::{error, info, warn};
+use proto::{
+    message::{Push, Request},
+    MessageType,
+};
+use protobuf::Message as ProtoMessage;
+use serde_json::Value;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+use crate::{
+    frame,
+    script::{Fault, Reply, ScheduledPush, Script},
+};
+
+#[derive(Clone)]
+enum Control {
+    Push(Push),
+    Disconnect,
+}
+
+enum Outbound {
+    Frame(Vec<u8>),
+    Close,
+}
+
+/// 每个 session 保留的推送数量, 更早的推送无法重发
+const SESSION_HISTORY: usize = 256;
+/// 连接断开后 session 的历史保留的时间, 之后重连的客户端无法请求重发
+const SESSION_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// 客户端请求重发推送的地址
+const PUSH_RESUME_URL: &str = "/push/resume";
+/// 客户端确认推送的地址
+const PUSH_ACK_URL: &str = "/push/ack";
+
+#[derive(Default)]
+struct SessionLog {
+    last_seq: u64,
+    history: VecDeque<Push>,
+    // 连接断开的时间, 连接中为 None
+    closed_at: Option<Instant>,
+}
+
+/// 清理断开超过 `SESSION_TTL` 的 session
+fn prune_sessions(sessions: &mut HashMap<String, SessionLog>, now: Instant) {
+    sessions.retain(|_, log| match log.closed_at {
+        Some(closed_at) => now.duration_since(closed_at) < SESSION_TTL,
+        None => true,
+    });
+}
+
+struct State {
+    replies: HashMap<String, Vec<Reply>>,
+    pushes: Vec<ScheduledPush>,
+    fallback: Reply,
+    // 每个 url 已经使用过的回复数量, 所有连接共享
+    served: Mutex<HashMap<String, usize>>,
+    next_session: AtomicU64,
+    sessions: Mutex<HashMap<String, SessionLog>>,
+    next_push_id: AtomicU64,
+    // 需要确认但还没有确认的推送, 所有连接共享
+    unacked: Mutex<Vec<Push>>,
+}
+
+impl State {
+    /// 每个连接一个 session, 连接断开后保留历史以便客户端重连后请求重发.
+    /// 新建时顺便清理过期的 session
+    fn new_session(&self) -> String {
+        let session = format!(
+            "mock-{}",
+            self.next_session.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let mut sessions = self.sessions.lock().unwrap();
+        prune_sessions(&mut sessions, Instant::now());
+        sessions.insert(session.clone(), SessionLog::default());
+        session
+    }
+
+    /// 连接断开, 历史在 `SESSION_TTL` 之后清理
+    fn close_session(&self, session: &str) {
+        if let Some(log) = self.sessions.lock().unwrap().get_mut(session) {
+            log.closed_at = Some(Instant::now());
+        }
+    }
+
+    /// 给推送编号并记录下来, 需要确认的推送第一次发送时分配 id
+    fn stamp(&self, session: &str, mut push: Push) -> Vec<u8> {
+        if push.ack && push.id.is_empty() {
+            push.id = format!(
+                "push-{}",
+                self.next_push_id.fetch_add(1, Ordering::Relaxed) + 1
+            );
+            self.unacked.lock().unwrap().push(push.clone());
+        }
+        let mut sessions = self.sessions.lock().unwrap();
+        let log = sessions.entry(session.to_string()).or_default();
+        log.last_seq += 1;
+        push.seq = log.last_seq;
+        push.session = session.to_string();
+        log.history.push_back(push.clone());
+        if log.history.len() > SESSION_HISTORY {
+            log.history.pop_front();
+        }
+        frame::encode(MessageType::PUSH, &push)
+    }
+
+    /// session 中 seq 大于 `after` 的推送, 已经不在历史中时返回 None
+    fn replay(&self, session: &str, after: u64) -> Option<Vec<Push>> {
+        let sessions = self.sessions.lock().unwrap();
+        let log = sessions.get(session)?;
+        let oldest = log
+            .history
+            .front()
+            .map_or(log.last_seq + 1, |push| push.seq);
+        if after + 1 < oldest || after > log.last_seq {
+            return None;
+        }
+        Some(
+            log.history
+                .iter()
+                .filter(|push| push.seq > after)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    fn ack(&self, ids: &[String]) -> usize {
+        let mut unacked = self.unacked.lock().unwrap();
+        let before = unacked.len();
+        unacked.retain(|push| !ids.contains(&push.id));
+        before - unacked.len()
+    }
+
+    /// 丢弃所有 session 的历史, 模拟服务端重启
+    fn forget_sessions(&self) {
+        self.sessions.lock().unwrap().clear();
+    }
+
+    fn next_reply(&self, url: &str) -> Reply {
+        match self.replies.get(url) {
+            Some(replies) if !replies.is_empty() => {
+                let mut served = self.served.lock().unwrap();
+                let count = served.entry(url.to_string()).or_insert(0);
+                let reply = replies[(*count).min(replies.len() - 1)].clone();
+                *count += 1;
+                reply
+            }
+            _ => self.fallback.clone(),
+        }
+    }
+}
+
+pub struct MockServer {
+    script: Script,
+}
+
+impl MockServer {
+    pub fn new(script: Script) -> Self {
+        Self { script }
+    }
+
+    /// 监听地址并在后台开始服务, `127.0.0.1:0` 会分配一个空闲端口
+    pub async fn bind(self, addr: &str) -> Result<MockHandle> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let (control, _) = broadcast::channel(64);
+        let state = Arc::new(State {
+            replies: self.script.replies(),
+            pushes: self.script.pushes.clone(),
+            fallback: self
+                .script
+                .fallback
+                .clone()
+                .unwrap_or_else(|| Reply::error(404, Value::String("not found".to_string()))),
+            served: Mutex::new(HashMap::new()),
+            next_session: AtomicU64::new(0),
+            sessions: Mutex::new(HashMap::new()),
+            next_push_id: AtomicU64::new(0),
+            unacked: Mutex::new(vec![]),
+        });
+
+        let s_control = control.clone();
+        let s_state = state.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer)) => {
+                        info!("mock server accept: {}", peer);
+                        let state = state.clone();
+                        let control = s_control.subscribe();
+                        tokio::spawn(async move {
+                            if let Err(err) = serve_conn(stream, state, control).await {
+                                warn!("mock server connection {} error: {}", peer, err);
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        error!("mock server accept error: {}", err);
+                    }
+                }
+            }
+        });
+
+        Ok(MockHandle {
+            addr: local_addr,
+            control,
+            state: s_state,
+            task,
+        })
+    }
+}
+
+/// 运行中的 mock 服务, drop 时不会停止服务, 需要显式调用 `shutdown`
+pub struct MockHandle {
+    addr: SocketAddr,
+    control: broadcast::Sender<Control>,
+    state: Arc<State>,
+    task: JoinHandle<()>,
+}
+
+impl MockHandle {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 立即向所有连接发送一个 `Push`
+    pub fn push(&self, event: &str, status: i32, data: Value) {
+        let _ = self
+            .control
+            .send(Control::Push(frame::push(event, status, data)));
+    }
+
+    /// 立即向所有连接发送一个需要确认的 `Push`
+    pub fn push_reliable(&self, event: &str, status: i32, data: Value) {
+        let mut push = frame::push(event, status, data);
+        push.ack = true;
+        let _ = self.control.send(Control::Push(push));
+    }
+
+    /// 还没有被确认的推送数量
+    pub fn unacked(&self) -> usize {
+        self.state.unacked.lock().unwrap().len()
+    }
+
+    /// 断开所有已建立的连接, 服务本身继续监听
+    pub fn disconnect_all(&self) {
+        let _ = self.control.send(Control::Disconnect);
+    }
+
+    /// 丢弃推送历史, 之后客户端请求重发会失败
+    pub fn forget_sessions(&self) {
+        self.state.forget_sessions();
+    }
+
+    pub fn shutdown(self) {
+        self.disconnect_all();
+        self.task.abort();
+    }
+}
+
+async fn serve_conn(
+    stream: TcpStream,
+    state: Arc<State>,
+    mut control: broadcast::Receiver<Control>,
+) -> Result<()> {
+    let ws = accept_async(stream).await?;
+    let (mut sink, mut source) = ws.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<Outbound>();
+    let session = state.new_session();
+
+    // 上次连接没有确认的推送使用原来的 id 重发
+    let unacked = state.unacked.lock().unwrap().clone();
+    for push in unacked {
+        let _ = tx.send(Outbound::Frame(state.stamp(&session, push)));
+    }
+
+    let timers: Vec<JoinHandle<()>> = state
+        .pushes
+        .iter()
+        .cloned()
+        .map(|push| {
+            tokio::spawn(schedule_push(
+                push,
+                state.clone(),
+                session.clone(),
+                tx.clone(),
+            ))
+        })
+        .collect();
+
+    let res = loop {
+        tokio::select! {
+            message = source.next() => match message {
+                Some(Ok(Message::Binary(data))) => handle_frame(&state, &data, &tx),
+                Some(Ok(Message::Text(text))) => handle_frame(&state, text.as_bytes(), &tx),
+                Some(Ok(Message::Close(_))) | None => break Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(err)) => break Err(err.into()),
+            },
+            outbound = rx.recv() => match outbound {
+                Some(Outbound::Frame(data)) => {
+                    if let Err(err) = sink.send(Message::Binary(data)).await {
+                        break Err(err.into());
+                    }
+                }
+                Some(Outbound::Close) | None => {
+                    let _ = sink.close().await;
+                    break Ok(());
+                }
+            },
+            control = control.recv() => match control {
+                Ok(Control::Push(push)) => {
+                    let _ = tx.send(Outbound::Frame(state.stamp(&session, push)));
+                }
+                Ok(Control::Disconnect) => {
+                    let _ = tx.send(Outbound::Close);
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break Ok(()),
+            },
+        }
+    };
+
+    for timer in timers {
+        timer.abort();
+    }
+    state.close_session(&session);
+    res
+}
+
+fn handle_frame(state: &Arc<State>, payload: &[u8], tx: &mpsc::UnboundedSender<Outbound>) {
+    if payload.is_empty() {
+        return;
+    }
+    let received = proto::now_ms();
+    match MessageType::from_u8(payload[0]) {
+        MessageType::REQUEST => match Request::parse_from_bytes(&payload[1..]) {
+            Ok(request) if request.url == PUSH_RESUME_URL => resume(state, &request, received, tx),
+            Ok(request) if request.url == PUSH_ACK_URL => {
+                let ids: Vec<String> =
+                    serde_json::from_value(request.data.json_value()["ids"].clone())
+                        .unwrap_or_default();
+                let count = state.ack(&ids);
+                let reply = Reply::ok(serde_json::json!({ "acked": count }));
+                let response = frame::response(&request.sequence, received, &reply);
+                let _ = tx.send(Outbound::Frame(frame::encode(
+                    MessageType::RESPONSE,
+                    &response,
+                )));
+            }
+            Ok(request) => {
+                let reply = state.next_reply(&request.url);
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    if reply.delay_ms > 0 {
+                        tokio::time::sleep(tokio::time::Duration::from_millis(reply.delay_ms))
+                            .await;
+                    }
+                    match reply.fault {
+                        Fault::None => {
+                            let response = frame::response(&request.sequence, received, &reply);
+                            let _ = tx.send(Outbound::Frame(frame::encode(
+                                MessageType::RESPONSE,
+                                &response,
+                            )));
+                        }
+                        Fault::Drop => {
+                            info!("mock server drop request: {}", request.url);
+                        }
+                        Fault::Disconnect => {
+                            let _ = tx.send(Outbound::Close);
+                        }
+                    }
+                });
+            }
+            Err(err) => {
+                error!("mock server parse request error: {}", err);
+            }
+        },
+        _ => {
+            info!("mock server ignore frame: {}", payload[0]);
+        }
+    }
+}
+
+/// 先重发历史中的推送再回复, 客户端收到回复时据此判断是否已经补齐
+fn resume(
+    state: &Arc<State>,
+    request: &Request,
+    received: i64,
+    tx: &mpsc::UnboundedSender<Outbound>,
+) {
+    let body = request.data.json_value();
+    let session = body["session"].as_str().unwrap_or_default();
+    let after = body["after"].as_u64().unwrap_or_default();
+    let reply = match state.replay(session, after) {
+        Some(pushes) => {
+            info!(
+                "mock server replay {} pushes of {} after {}",
+                pushes.len(),
+                session,
+                after
+            );
+            let count = pushes.len();
+            for push in pushes {
+                let _ = tx.send(Outbound::Frame(frame::encode(MessageType::PUSH, &push)));
+            }
+            Reply::ok(serde_json::json!({ "replayed": count }))
+        }
+        None => Reply::error(404, Value::String("session expired".to_string())),
+    };
+    let response = frame::response(&request.sequence, received, &reply);
+    let _ = tx.send(Outbound::Frame(frame::encode(
+        MessageType::RESPONSE,
+        &response,
+    )));
+}
+
+async fn schedule_push(
+    push: ScheduledPush,
+    state: Arc<State>,
+    session: String,
+    tx: mpsc::UnboundedSender<Outbound>,
+) {
+    tokio::time::sleep(tokio::time::Duration::from_millis(push.after_ms)).await;
+    let mut sent = 0;
+    loop {
+        let mut message = frame::push(&push.event, push.status, push.data.clone());
+        message.ack = push.ack;
+        let data = state.stamp(&session, message);
+        if tx.send(Outbound::Frame(data)).is_err() {
+            return;
+        }
+        sent += 1;
+        if push.times.is_some_and(|times| sent >= times) {
+            return;
+        }
+        match push.every_ms {
+            Some(every_ms) => {
+                tokio::time::sleep(tokio::time::Duration::from_millis(every_ms)).await;
+            }
+            None => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_sessions_expire() {
+        let now = Instant::now();
+        let mut sessions = HashMap::new();
+        sessions.insert("open".to_string(), SessionLog::default());
+        for (name, closed_at) in [("old", now), ("recent", now + SESSION_TTL)] {
+            sessions.insert(
+                name.to_string(),
+                SessionLog {
+                    closed_at: Some(closed_at),
+                    ..SessionLog::default()
+                },
+            );
+        }
+        prune_sessions(&mut sessions, now + SESSION_TTL);
+        let mut names: Vec<&String> = sessions.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["open", "recent"]);
+        // 连接中的 session 不会过期
+        prune_sessions(&mut sessions, now + SESSION_TTL * 3);
+        assert_eq!(sessions.keys().collect::<Vec<_>>(), vec!["open"]);
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use protobuf::{
+    descriptor::{
+        DescriptorProto, EnumDescriptorProto, FileDescriptorProto, ServiceDescriptorProto,
+    },
+    UnknownValueRef,
+};
+use protobuf_parse::ParsedAndTypechecked;
+
+/// proto 文件所在的目录, 同时作为 import 的根目录
+const PROTO_DIR: &str = "protos";
+
+/// `rpc.proto` 中 `url` 选项的字段号
+const URL_OPTION: u32 = 50000;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+fn proto_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            proto_files(&path, files)?;
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("proto") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, ch) in name.chars().enumerate() {
+        if ch.is_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+fn fn_name(name: &str) -> String {
+    let name = snake_case(name);
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+/// 与关键字同名的模块, protobuf-codegen 会加上 `_` 后缀, 例如 `struct_`
+fn module_name(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// proto 文件对应的 Rust 模块, 与 protobuf-codegen 的命名一致
+fn file_module(file: &FileDescriptorProto) -> String {
+    let stem = Path::new(file.name())
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .replace(|ch: char| !ch.is_ascii_alphanumeric(), "_");
+    let stem = module_name(&stem);
+    if file.name() == "google/protobuf/descriptor.proto" {
+        "::protobuf::descriptor".to_string()
+    } else if file.name().starts_with("google/protobuf/") {
+        format!("::protobuf::well_known_types::{}", stem)
+    } else {
+        format!("crate::{}", stem)
+    }
+}
+
+/// 记录 `.package.Message` 到 Rust 路径的映射, 嵌套的消息在外层消息同名的模块中
+fn message_paths(
+    proto_prefix: &str,
+    rust_prefix: &str,
+    messages: &[DescriptorProto],
+    paths: &mut HashMap<String, String>,
+) {
+    for message in messages {
+        let proto_name = format!("{}.{}", proto_prefix, message.name());
+        paths.insert(
+            proto_name.clone(),
+            format!("{}::{}", rust_prefix, message.name()),
+        );
+        message_paths(
+            &proto_name,
+            &format!(
+                "{}::{}",
+                rust_prefix,
+                module_name(&snake_case(message.name()))
+            ),
+            &message.nested_type,
+            paths,
+        );
+    }
+}
+
+fn method_url(
+    service: &ServiceDescriptorProto,
+    method: &str,
+    options: &protobuf::descriptor::MethodOptions,
+) -> String {
+    match options.special_fields.unknown_fields().get(URL_OPTION) {
+        Some(UnknownValueRef::LengthDelimited(url)) => String::from_utf8_lossy(url).to_string(),
+        _ => format!(
+            "/{}/{}",
+            service
+                .name()
+                .strip_suffix("Service")
+                .unwrap_or_else(|| service.name())
+                .to_lowercase(),
+            method.to_lowercase()
+        ),
+    }
+}
+
+fn service_client(
+    file: &FileDescriptorProto,
+    service: &ServiceDescriptorProto,
+    paths: &HashMap<String, String>,
+) -> String {
+    let name = format!("{}Client", service.name());
+    let rust_path = |proto_name: &str| {
+        paths
+            .get(proto_name)
+            .unwrap_or_else(|| panic!("unknown message {} in {}", proto_name, service.name()))
+            .clone()
+    };
+    let mut methods = String::new();
+    for method in service.method.iter() {
+        let url = method_url(service, method.name(), &method.options);
+        methods.push_str(&format!(
+            "
+    /// `{url}`
+    pub async fn {fn_name}(
+        &mut self,
+        request: &{input},
+    ) -> Result<{output}, crate::service::RpcError> {{
+        crate::service::call(&mut self.transport, \"{url}\", request).await
+    }}
+",
+            url = url,
+            fn_name = fn_name(method.name()),
+            input = rust_path(method.input_type()),
+            output = rust_path(method.output_type()),
+        ));
+    }
+    format!(
+        "
+/// `{package}.{service}` 的客户端
+#[derive(Debug, Clone)]
+pub struct {name}<T> {{
+    transport: T,
+}}
+
+impl<T> From<T> for {name}<T> {{
+    fn from(transport: T) -> Self {{
+        Self {{ transport }}
+    }}
+}}
+
+impl<T: crate::service::RpcTransport> {name}<T> {{
+    pub fn new(transport: T) -> Self {{
+        Self {{ transport }}
+    }}
+
+    pub fn into_inner(self) -> T {{
+        self.transport
+    }}
+{methods}}}
+",
+        package = file.package(),
+        service = service.name(),
+        name = name,
+        methods = methods
+    )
+}
+
+/// 输入的文件, 不包括依赖的 google/protobuf/*.proto
+fn input_files(parsed: &ParsedAndTypechecked) -> impl Iterator<Item = &FileDescriptorProto> {
+    parsed.file_descriptors.iter().filter(|file| {
+        parsed
+            .relative_paths
+            .iter()
+            .any(|path| path.to_str() == file.name())
+    })
+}
+
+/// 为 proto 中的 service 生成客户端, 每个 rpc 按 url 发送请求, 请求和响应以 JSON 放在 Body 中
+fn generate_services(parsed: &ParsedAndTypechecked, out_file: &Path) {
+    let mut paths = HashMap::new();
+    for file in parsed.file_descriptors.iter() {
+        let proto_prefix = if file.package().is_empty() {
+            String::new()
+        } else {
+            format!(".{}", file.package())
+        };
+        message_paths(
+            &proto_prefix,
+            &file_module(file),
+            &file.message_type,
+            &mut paths,
+        );
+    }
+
+    let mut code = "// @generated by build.rs from protos/**/*.proto\n".to_string();
+    for file in input_files(parsed) {
+        for service in file.service.iter() {
+            code.push_str(&service_client(file, service, &paths));
+        }
+    }
+    fs::write(out_file, code).expect("write services.rs");
+}
+
+/// 收集消息和枚举的 Rust 路径, 嵌套的类型在外层消息同名的模块中
+fn serde_types(
+    rust_prefix: &str,
+    messages: &[DescriptorProto],
+    enums: &[EnumDescriptorProto],
+    types: &mut Vec<String>,
+) {
+    for item in enums {
+        types.push(format!("enum {}::{}", rust_prefix, item.name()));
+    }
+    for message in messages {
+        // map 字段的 entry 不会生成结构体
+        if message.options.map_entry() {
+            continue;
+        }
+        types.push(format!("message {}::{}", rust_prefix, message.name()));
+        serde_types(
+            &format!(
+                "{}::{}",
+                rust_prefix,
+                module_name(&snake_case(message.name()))
+            ),
+            &message.nested_type,
+            &message.enum_type,
+            types,
+        );
+    }
+}
+
+/// 所有生成的消息和枚举按 proto3 的 JSON 映射实现 serde
+fn generate_serde(parsed: &ParsedAndTypechecked, out_file: &Path) {
+    let mut types = vec![];
+    for file in input_files(parsed) {
+        serde_types(
+            &file_module(file),
+            &file.message_type,
+            &file.enum_type,
+            &mut types,
+        );
+    }
+    let mut code = "// @generated by build.rs from protos/**/*.proto\n".to_string();
+    for item in types {
+        code.push_str(&format!("proto_serde!({});\n", item));
+    }
+    fs::write(out_file, code).expect("write serde.rs");
+}
+
+/// 生成的所有文件的描述, 运行时加载的描述可以 import 这些文件
+fn generate_files(parsed: &ParsedAndTypechecked, out_file: &Path) {
+    let mut code = "// @generated by build.rs from protos/**/*.proto\n".to_string();
+    code.push_str(
+        "pub(crate) const GENERATED_FILES: &[fn() -> &'static ::protobuf::reflect::FileDescriptor] = &[\n",
+    );
+    for file in input_files(parsed) {
+        code.push_str(&format!("    {}::file_descriptor,\n", file_module(file)));
+    }
+    code.push_str("];\n");
+    fs::write(out_file, code).expect("write files.rs");
+}
+
+/// 用纯 Rust 的解析器从 `protos/**/*.proto` 生成代码, 不需要安装 protoc.
+/// 每个 proto 文件生成一个同名模块, 写入 `$OUT_DIR/protos`; service 的客户端写入 `$OUT_DIR/services.rs`,
+/// 所有文件的描述写入 `$OUT_DIR/files.rs`, 消息和枚举的 serde 实现写入 `$OUT_DIR/serde.rs`.
+/// 都由 lib.rs include
+fn main() {
+    let mut files = vec![];
+    proto_files(Path::new(PROTO_DIR), &mut files).expect("read proto dir");
+    // 保证生成的 mod.rs 顺序稳定
+    files.sort();
+
+    println!("cargo:rerun-if-changed={}", PROTO_DIR);
+    for file in files.iter() {
+        println!("cargo:rerun-if-changed={}", file.display());
+    }
+
+    protobuf_codegen::Codegen::new()
+        .pure()
+        .include(PROTO_DIR)
+        .inputs(&files)
+        .cargo_out_dir(PROTO_DIR)
+        .run_from_script();
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR"));
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .include(PROTO_DIR)
+        .inputs(&files)
+        .parse_and_typecheck()
+        .expect("parse proto files");
+    generate_services(&parsed, &out_dir.join("services.rs"));
+    generate_files(&parsed, &out_dir.join("files.rs"));
+    generate_serde(&parsed, &out_dir.join("serde.rs"));
+}
@@ -0,0 +1,346 @@
+//! 运行时加载的 proto 描述, 服务端插件新增的消息类型不需要重新编译就可以解码和编码.
+//! 描述来自 `protoc --include_imports --descriptor_set_out` 生成的 FileDescriptorSet,
+//! 可以从文件加载, 也可以在连接时从服务端获取. 同名的文件后加载的覆盖先加载的.
+//! 描述在进程内共享, 与编译时生成的类型或者其它文件中的类型同名的消息不会被加载
+
+use std::{collections::HashMap, fs, io, path::Path, sync::RwLock};
+
+use once_cell::sync::Lazy;
+use protobuf::{
+    descriptor::{FileDescriptorProto, FileDescriptorSet},
+    reflect::{FileDescriptor, MessageDescriptor},
+    well_known_types, Message,
+};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::json::{merge_json, to_json_with_unknown, JsonError};
+
+// 由 build.rs 生成, 所有 protos/**/*.proto 的描述
+include!(concat!(env!("OUT_DIR"), "/files.rs"));
+
+/// FileDescriptorSet 本身的类型名, 服务端用它返回描述
+pub const FILE_DESCRIPTOR_SET: &str = "google.protobuf.FileDescriptorSet";
+
+#[derive(Debug, Error)]
+pub enum DescriptorError {
+    #[error("read descriptor set error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid descriptor set: {0}")]
+    Invalid(#[from] protobuf::Error),
+    #[error("unknown message type {0}")]
+    UnknownType(String),
+    #[error("message type {0} is already defined in {1}")]
+    Conflict(String, String),
+    #[error("{0}")]
+    Json(#[from] JsonError),
+}
+
+/// 编译时生成的文件, 加载的描述 import 它们时不需要包含在 FileDescriptorSet 中
+fn builtin_files() -> Vec<FileDescriptor> {
+    let mut files = vec![
+        protobuf::descriptor::file_descriptor().clone(),
+        well_known_types::any::file_descriptor().clone(),
+        well_known_types::api::file_descriptor().clone(),
+        well_known_types::duration::file_descriptor().clone(),
+        well_known_types::empty::file_descriptor().clone(),
+        well_known_types::field_mask::file_descriptor().clone(),
+        well_known_types::source_context::file_descriptor().clone(),
+        well_known_types::struct_::file_descriptor().clone(),
+        well_known_types::timestamp::file_descriptor().clone(),
+        well_known_types::type_::file_descriptor().clone(),
+        well_known_types::wrappers::file_descriptor().clone(),
+    ];
+    files.extend(GENERATED_FILES.iter().map(|file| file().clone()));
+    files
+}
+
+fn index_messages(
+    messages: impl Iterator<Item = MessageDescriptor>,
+    index: &mut HashMap<String, MessageDescriptor>,
+) {
+    for message in messages {
+        index_messages(message.nested_messages(), index);
+        index.insert(message.full_name().to_string(), message);
+    }
+}
+
+#[derive(Default)]
+struct Loaded {
+    // 文件名 -> 描述
+    files: HashMap<String, FileDescriptor>,
+    // 消息的完整名称 (没有开头的 `.`) -> 描述
+    messages: HashMap<String, MessageDescriptor>,
+}
+
+/// 按完整名称查找消息类型, 包括编译时生成的和运行时加载的
+pub struct DescriptorPool {
+    builtin: Vec<FileDescriptor>,
+    builtin_messages: HashMap<String, MessageDescriptor>,
+    loaded: RwLock<Loaded>,
+}
+
+impl Default for DescriptorPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DescriptorPool {
+    pub fn new() -> Self {
+        let builtin = builtin_files();
+        let mut builtin_messages = HashMap::new();
+        index_messages(
+            builtin.iter().flat_map(|file| file.messages()),
+            &mut builtin_messages,
+        );
+        Self {
+            builtin,
+            builtin_messages,
+            loaded: RwLock::new(Loaded::default()),
+        }
+    }
+
+    /// 加载 FileDescriptorSet 的编码, 返回其中的消息类型
+    pub fn load_set(&self, bytes: &[u8]) -> Result<Vec<String>, DescriptorError> {
+        let set = FileDescriptorSet::parse_from_bytes(bytes)?;
+        self.load_protos(set.file)
+    }
+
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<Vec<String>, DescriptorError> {
+        self.load_set(&fs::read(path)?)
+    }
+
+    fn load_protos(
+        &self,
+        protos: Vec<FileDescriptorProto>,
+    ) -> Result<Vec<String>, DescriptorError> {
+        let mut loaded = self.loaded.write().unwrap();
+        // 编译时生成的文件不会被覆盖, set 中包含的同名文件直接忽略
+        let protos: Vec<FileDescriptorProto> = protos
+            .into_iter()
+            .filter(|proto| !self.builtin.iter().any(|file| file.name() == proto.name()))
+            .collect();
+        let mut dependencies = self.builtin.clone();
+        dependencies.extend(
+            loaded
+                .files
+                .values()
+                .filter(|file| !protos.iter().any(|proto| proto.name() == file.name()))
+                .cloned(),
+        );
+        let files = FileDescriptor::new_dynamic_fds(protos, &dependencies)?;
+
+        let mut messages = HashMap::new();
+        for file in files.iter() {
+            index_messages(file.messages(), &mut messages);
+        }
+        for name in messages.keys() {
+            let existing = self.builtin_messages.get(name).or_else(|| {
+                loaded.messages.get(name).filter(|message| {
+                    let file = message.file_descriptor().name();
+                    !files.iter().any(|loading| loading.name() == file)
+                })
+            });
+            if let Some(existing) = existing {
+                return Err(DescriptorError::Conflict(
+                    name.clone(),
+                    existing.file_descriptor().name().to_string(),
+                ));
+            }
+        }
+        let mut names: Vec<String> = messages.keys().cloned().collect();
+        names.sort();
+        for file in files {
+            loaded.files.insert(file.name().to_string(), file);
+        }
+        // 重新索引, 覆盖的文件中删除的类型不再保留
+        loaded.messages.clear();
+        let Loaded { files, messages } = &mut *loaded;
+        index_messages(files.values().flat_map(|file| file.messages()), messages);
+        Ok(names)
+    }
+
+    pub fn message(&self, name: &str) -> Option<MessageDescriptor> {
+        let name = name.trim_start_matches('.');
+        if let Some(message) = self.builtin_messages.get(name) {
+            return Some(message.clone());
+        }
+        self.loaded.read().unwrap().messages.get(name).cloned()
+    }
+
+    /// 运行时加载的消息类型
+    pub fn messages(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .loaded
+            .read()
+            .unwrap()
+            .messages
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// 按类型解码为 JSON, 不认识的字段放在 `@unknown` 中
+    pub fn decode(&self, name: &str, bytes: &[u8]) -> Result<Value, DescriptorError> {
+        let descriptor = self
+            .message(name)
+            .ok_or_else(|| DescriptorError::UnknownType(name.to_string()))?;
+        let message = descriptor.parse_from_bytes(bytes)?;
+        Ok(to_json_with_unknown(&*message))
+    }
+
+    /// 按 proto3 的 JSON 映射编码为该类型
+    pub fn encode(&self, name: &str, value: &Value) -> Result<Vec<u8>, DescriptorError> {
+        let descriptor = self
+            .message(name)
+            .ok_or_else(|| DescriptorError::UnknownType(name.to_string()))?;
+        let mut message = descriptor.new_instance();
+        merge_json(&mut *message, value)?;
+        Ok(message.write_to_bytes_dyn()?)
+    }
+}
+
+static POOL: Lazy<DescriptorPool> = Lazy::new(DescriptorPool::new);
+
+/// 进程内共享的描述, `Body` 解码 payload 时使用
+pub fn pool() -> &'static DescriptorPool {
+    &POOL
+}
+
+#[cfg(test)]
+mod tests {
+    use protobuf::descriptor::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn proto(
+        name: &str,
+        package: &str,
+        message: &str,
+        fields: &[(&str, Type)],
+    ) -> FileDescriptorProto {
+        let mut descriptor = DescriptorProto::new();
+        descriptor.set_name(message.to_string());
+        for (index, (name, kind)) in fields.iter().enumerate() {
+            let mut field = FieldDescriptorProto::new();
+            field.set_name(name.to_string());
+            field.set_json_name(name.to_string());
+            field.set_number(index as i32 + 1);
+            field.set_label(Label::LABEL_OPTIONAL);
+            field.set_type(*kind);
+            descriptor.field.push(field);
+        }
+        let mut file = FileDescriptorProto::new();
+        file.set_name(name.to_string());
+        file.set_package(package.to_string());
+        file.set_syntax("proto3".to_string());
+        file.message_type.push(descriptor);
+        file
+    }
+
+    fn set(files: Vec<FileDescriptorProto>) -> Vec<u8> {
+        let mut set = FileDescriptorSet::new();
+        set.file = files;
+        set.write_to_bytes().unwrap()
+    }
+
+    fn report() -> FileDescriptorProto {
+        proto(
+            "plugin/report.proto",
+            "plugin",
+            "Report",
+            &[("name", Type::TYPE_STRING), ("count", Type::TYPE_INT64)],
+        )
+    }
+
+    #[test]
+    fn load_and_decode() {
+        let pool = DescriptorPool::new();
+        assert_eq!(
+            pool.load_set(&set(vec![report()])).unwrap(),
+            vec!["plugin.Report"]
+        );
+        assert_eq!(pool.messages(), vec!["plugin.Report"]);
+
+        let value = json!({ "name": "a", "count": "3" });
+        let mut bytes = pool.encode(".plugin.Report", &value).unwrap();
+        assert_eq!(pool.decode("plugin.Report", &bytes).unwrap(), value);
+        // 字段号 9 的 varint 1
+        bytes.extend_from_slice(&[0x48, 0x01]);
+        assert_eq!(
+            pool.decode("plugin.Report", &bytes).unwrap(),
+            json!({
+                "name": "a",
+                "count": "3",
+                "@unknown": [{ "field": 9, "wire": "varint", "value": "1" }],
+            })
+        );
+        assert!(matches!(
+            pool.decode("plugin.Missing", &bytes),
+            Err(DescriptorError::UnknownType(_))
+        ));
+    }
+
+    #[test]
+    fn builtin_types_are_not_shadowed() {
+        let pool = DescriptorPool::new();
+        // 与编译时生成的文件同名时直接忽略
+        let builtin = crate::message::file_descriptor().name().to_string();
+        let same_file = proto(&builtin, "message", "Push", &[("x", Type::TYPE_BOOL)]);
+        assert!(pool.load_set(&set(vec![same_file])).unwrap().is_empty());
+        // 其它文件中的同名类型不能加载
+        let other_file = proto(
+            "plugin/push.proto",
+            "message",
+            "Push",
+            &[("x", Type::TYPE_BOOL)],
+        );
+        assert!(matches!(
+            pool.load_set(&set(vec![other_file])),
+            Err(DescriptorError::Conflict(name, file)) if name == "message.Push" && file == builtin
+        ));
+        assert!(pool.messages().is_empty());
+        let push = pool.message("message.Push").unwrap();
+        assert_eq!(push.file_descriptor().name(), builtin);
+        assert!(push.field_by_name("event").is_some());
+    }
+
+    #[test]
+    fn loaded_types_conflict_across_files() {
+        let pool = DescriptorPool::new();
+        pool.load_set(&set(vec![report()])).unwrap();
+        let other = proto(
+            "other/report.proto",
+            "plugin",
+            "Report",
+            &[("id", Type::TYPE_STRING)],
+        );
+        assert!(matches!(
+            pool.load_set(&set(vec![other])),
+            Err(DescriptorError::Conflict(name, _)) if name == "plugin.Report"
+        ));
+        let report = pool.message("plugin.Report").unwrap();
+        assert!(report.field_by_name("count").is_some());
+
+        // 同名的文件覆盖之前加载的, 删除的类型不再保留
+        let replaced = proto(
+            "plugin/report.proto",
+            "plugin",
+            "Summary",
+            &[("total", Type::TYPE_UINT64)],
+        );
+        assert_eq!(
+            pool.load_set(&set(vec![replaced])).unwrap(),
+            vec!["plugin.Summary"]
+        );
+        assert_eq!(pool.messages(), vec!["plugin.Summary"]);
+        assert!(pool.message("plugin.Report").is_none());
+    }
+}
@@ -0,0 +1,955 @@
+//! proto3 的 JSON 映射, 通过反射实现, 对生成的消息和动态消息都有效.
+//! 字段使用 json_name, 默认值不输出, 枚举使用名称, 64 位整数使用字符串, bytes 使用 base64.
+//! 解析时同时接受 json_name 和原始字段名, 未知字段直接忽略.
+//! Timestamp, Duration, FieldMask, Any 等 well-known 类型使用规定的特殊格式.
+//! 所有生成的消息和枚举都按这里的映射实现了 serde 的 Serialize 和 Deserialize
+
+use protobuf::{
+    reflect::{
+        EnumDescriptor, FieldDescriptor, ReflectFieldRef, ReflectValueBox, ReflectValueRef,
+        RuntimeFieldType, RuntimeType,
+    },
+    MessageDyn, MessageFull, UnknownValueRef,
+};
+use serde_json::{json, Map, Number, Value};
+use thiserror::Error;
+
+use crate::descriptors::pool;
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct JsonError(String);
+
+impl From<protobuf::Error> for JsonError {
+    fn from(error: protobuf::Error) -> Self {
+        JsonError(error.to_string())
+    }
+}
+
+fn error<T>(message: String) -> Result<T, JsonError> {
+    Err(JsonError(message))
+}
+
+const NULL_VALUE: &str = "google.protobuf.NullValue";
+
+/// Any 的 JSON 中类型的 key
+const ANY_TYPE: &str = "@type";
+
+/// `to_json_with_unknown` 中未知字段的 key
+pub const UNKNOWN_FIELDS: &str = "@unknown";
+
+/// 超过这个值的浮点数不能精确表示为整数
+const MAX_SAFE_INTEGER: f64 = 9007199254740992.0;
+
+/// 包装类型在 JSON 中就是其中 `value` 字段的值
+fn is_wrapper(full_name: &str) -> bool {
+    matches!(
+        full_name,
+        "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue"
+    )
+}
+
+/// 按名称取字段, 只用于结构固定的 well-known 类型
+fn field(message: &dyn MessageDyn, name: &str) -> FieldDescriptor {
+    message
+        .descriptor_dyn()
+        .field_by_name(name)
+        .unwrap_or_else(|| panic!("{} has no field {}", message.descriptor_dyn(), name))
+}
+
+fn float_json(value: f64) -> Value {
+    if value.is_nan() {
+        Value::String("NaN".to_string())
+    } else if value.is_infinite() {
+        Value::String(if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string())
+    } else if value.fract() == 0.0 && value.abs() < MAX_SAFE_INTEGER {
+        // 整数值不带小数点输出, 例如 Struct 中的 1 仍然是 1
+        Value::from(value as i64)
+    } else {
+        Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn enum_json(descriptor: &EnumDescriptor, number: i32) -> Value {
+    if descriptor.full_name() == NULL_VALUE {
+        return Value::Null;
+    }
+    match descriptor.value_by_number(number) {
+        Some(value) => Value::String(value.name().to_string()),
+        None => Value::from(number),
+    }
+}
+
+fn value_json(value: &ReflectValueRef, unknown: bool) -> Value {
+    match value {
+        ReflectValueRef::U32(value) => Value::from(*value),
+        ReflectValueRef::I32(value) => Value::from(*value),
+        ReflectValueRef::U64(value) => Value::String(value.to_string()),
+        ReflectValueRef::I64(value) => Value::String(value.to_string()),
+        // 按 f32 的最短表示转换, 避免 0.1 变成 0.10000000149011612
+        ReflectValueRef::F32(value) => float_json(value.to_string().parse().unwrap_or_default()),
+        ReflectValueRef::F64(value) => float_json(*value),
+        ReflectValueRef::Bool(value) => Value::Bool(*value),
+        ReflectValueRef::String(value) => Value::String(value.to_string()),
+        ReflectValueRef::Bytes(value) => Value::String(base64::encode(value)),
+        ReflectValueRef::Enum(descriptor, number) => enum_json(descriptor, *number),
+        ReflectValueRef::Message(message) => message_json(&**message, unknown),
+    }
+}
+
+/// map 的 key 在 JSON 中总是字符串
+fn key_json(key: &ReflectValueRef) -> String {
+    match value_json(key, false) {
+        Value::String(key) => key,
+        key => key.to_string(),
+    }
+}
+
+fn well_known_json(message: &dyn MessageDyn, unknown: bool) -> Option<Value> {
+    let full_name = message.descriptor_dyn().full_name().to_string();
+    if is_wrapper(&full_name) {
+        let value = field(message, "value").get_singular_field_or_default(message);
+        return Some(value_json(&value, unknown));
+    }
+    match full_name.as_str() {
+        "google.protobuf.Struct" => {
+            let fields = field(message, "fields").get_map(message);
+            let object = fields
+                .into_iter()
+                .map(|(key, value)| (key_json(&key), value_json(&value, unknown)))
+                .collect();
+            Some(Value::Object(object))
+        }
+        "google.protobuf.ListValue" => {
+            let values = field(message, "values").get_repeated(message);
+            Some(Value::Array(
+                (0..values.len())
+                    .map(|index| value_json(&values.get(index), unknown))
+                    .collect(),
+            ))
+        }
+        // oneof kind 中设置了的那个字段, 都没有设置时为 null
+        "google.protobuf.Value" => Some(
+            message
+                .descriptor_dyn()
+                .fields()
+                .find_map(|field| field.get_singular(message))
+                .map_or(Value::Null, |value| value_json(&value, unknown)),
+        ),
+        "google.protobuf.Timestamp" => Some(Value::String(format_timestamp(
+            int_field(message, "seconds"),
+            int_field(message, "nanos") as i32,
+        ))),
+        "google.protobuf.Duration" => Some(Value::String(format_duration(
+            int_field(message, "seconds"),
+            int_field(message, "nanos") as i32,
+        ))),
+        "google.protobuf.FieldMask" => {
+            let paths = field(message, "paths").get_repeated(message);
+            let paths: Vec<String> = (0..paths.len())
+                .map(|index| lower_camel(paths.get(index).to_str().unwrap_or_default()))
+                .collect();
+            Some(Value::String(paths.join(",")))
+        }
+        "google.protobuf.Any" => any_json(message, unknown),
+        _ => None,
+    }
+}
+
+/// Any 中的类型已知时展开为 `{"@type": url, ...}`, well-known 类型的值放在 `value` 中.
+/// 类型未知时按普通消息输出
+fn any_json(message: &dyn MessageDyn, unknown: bool) -> Option<Value> {
+    let type_url = field(message, "type_url").get_singular_field_or_default(message);
+    let type_url = type_url.to_str().unwrap_or_default();
+    let bytes = field(message, "value").get_singular_field_or_default(message);
+    let descriptor = pool().message(any_type_name(type_url))?;
+    let inner = descriptor.parse_from_bytes(bytes.to_bytes()?).ok()?;
+    let mut object = match message_json(&*inner, unknown) {
+        Value::Object(object) if well_known_json(&*inner, unknown).is_none() => object,
+        value => {
+            let mut object = Map::new();
+            object.insert("value".to_string(), value);
+            object
+        }
+    };
+    object.insert(ANY_TYPE.to_string(), Value::String(type_url.to_string()));
+    Some(Value::Object(object))
+}
+
+/// `type.googleapis.com/package.Message` 中最后一个 `/` 之后的类型名
+fn any_type_name(type_url: &str) -> &str {
+    type_url.rsplit('/').next().unwrap_or_default()
+}
+
+fn int_field(message: &dyn MessageDyn, name: &str) -> i64 {
+    match field(message, name).get_singular_field_or_default(message) {
+        ReflectValueRef::I64(value) => value,
+        ReflectValueRef::I32(value) => value as i64,
+        _ => 0,
+    }
+}
+
+fn lower_camel(path: &str) -> String {
+    let mut camel = String::new();
+    let mut upper = false;
+    for ch in path.chars() {
+        if ch == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(ch);
+        }
+    }
+    camel
+}
+
+fn snake(path: &str) -> String {
+    let mut snake = String::new();
+    for ch in path.chars() {
+        if ch.is_uppercase() {
+            snake.push('_');
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+/// 小数部分按 0, 3, 6 或 9 位输出
+fn format_nanos(nanos: u32) -> String {
+    if nanos == 0 {
+        return String::new();
+    }
+    let mut digits = format!("{:09}", nanos);
+    while digits.ends_with("000") {
+        digits.truncate(digits.len() - 3);
+    }
+    format!(".{}", digits)
+}
+
+/// 1970-01-01 之后的天数转换为 (年, 月, 日)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// RFC 3339 格式的 UTC 时间, 例如 `2023-06-01T08:00:00.123Z`
+pub fn format_timestamp(seconds: i64, nanos: i32) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        format_nanos(nanos.max(0) as u32)
+    )
+}
+
+/// 小数部分, 例如 `.5` 为 500000000 纳秒
+fn parse_nanos(fraction: &str) -> Option<i32> {
+    if fraction.is_empty() || fraction.len() > 9 || !fraction.chars().all(|ch| ch.is_ascii_digit())
+    {
+        return None;
+    }
+    format!("{:0<9}", fraction).parse().ok()
+}
+
+/// 解析 RFC 3339 格式的时间, 时区可以是 `Z` 或者 `+08:00`
+pub fn parse_timestamp(text: &str) -> Option<(i64, i32)> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let part = text.get(range)?;
+        if part.chars().all(|ch| ch.is_ascii_digit()) {
+            part.parse().ok()
+        } else {
+            None
+        }
+    };
+    // 下面按字节下标切分, 先保证都是 ascii
+    if !text.is_ascii() || text.len() < 20 {
+        return None;
+    }
+    let separators = [(4, '-'), (7, '-'), (13, ':'), (16, ':')];
+    if separators
+        .iter()
+        .any(|(index, separator)| !text[*index..].starts_with(*separator))
+        || !text[10..].starts_with(['T', 't'])
+    {
+        return None;
+    }
+    let (year, month, day) = (number(0..4)?, number(5..7)? as u32, number(8..10)? as u32);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    let mut rest = text.get(19..)?;
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let end = fraction
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        nanos = parse_nanos(&fraction[..end])?;
+        rest = &fraction[end..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.chars().next()? {
+                '+' => 1,
+                '-' => -1,
+                _ => return None,
+            };
+            let (hours, minutes) = rest[1..].split_once(':')?;
+            if hours.len() != 2 || minutes.len() != 2 {
+                return None;
+            }
+            sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60)
+        }
+    };
+    let days = days_from_civil(year, month, day);
+    // 2 月 30 日这样不存在的日期换算回来会变成另一天
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    let seconds = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    Some((seconds, nanos))
+}
+
+/// 秒数加上 `s` 后缀, 例如 `1.5s`, `-0.010s`
+pub fn format_duration(seconds: i64, nanos: i32) -> String {
+    let sign = if seconds < 0 || nanos < 0 { "-" } else { "" };
+    format!(
+        "{}{}{}s",
+        sign,
+        seconds.unsigned_abs(),
+        format_nanos(nanos.unsigned_abs())
+    )
+}
+
+pub fn parse_duration(text: &str) -> Option<(i64, i32)> {
+    let text = text.strip_suffix('s')?;
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (seconds, nanos) = match text.split_once('.') {
+        Some((seconds, fraction)) => (seconds, parse_nanos(fraction)?),
+        None => (text, 0),
+    };
+    if seconds.is_empty() || !seconds.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: i64 = seconds.parse().ok()?;
+    if negative {
+        Some((-seconds, -nanos))
+    } else {
+        Some((seconds, nanos))
+    }
+}
+
+/// 未知字段按字段号排序, 每个值带上 wire type, bytes 使用 base64
+pub fn unknown_json(message: &dyn MessageDyn) -> Option<Value> {
+    let mut fields: Vec<(u32, UnknownValueRef)> = message
+        .special_fields_dyn()
+        .unknown_fields()
+        .iter()
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+    fields.sort_by_key(|(number, _)| *number);
+    let fields = fields
+        .into_iter()
+        .map(|(number, value)| {
+            let (wire, value) = match value {
+                UnknownValueRef::Fixed32(value) => ("fixed32", Value::from(value)),
+                UnknownValueRef::Fixed64(value) => ("fixed64", Value::String(value.to_string())),
+                UnknownValueRef::Varint(value) => ("varint", Value::String(value.to_string())),
+                UnknownValueRef::LengthDelimited(value) => {
+                    ("bytes", Value::String(base64::encode(value)))
+                }
+            };
+            json!({ "field": number, "wire": wire, "value": value })
+        })
+        .collect();
+    Some(Value::Array(fields))
+}
+
+fn message_json(message: &dyn MessageDyn, unknown: bool) -> Value {
+    if let Some(value) = well_known_json(message, unknown) {
+        return value;
+    }
+    let mut object = Map::new();
+    for field in message.descriptor_dyn().fields() {
+        let value = match field.get_reflect(message) {
+            ReflectFieldRef::Optional(value) => match value.value() {
+                Some(value) => value_json(&value, unknown),
+                None => continue,
+            },
+            ReflectFieldRef::Repeated(values) if !values.is_empty() => Value::Array(
+                (0..values.len())
+                    .map(|index| value_json(&values.get(index), unknown))
+                    .collect(),
+            ),
+            ReflectFieldRef::Map(values) if !values.is_empty() => Value::Object(
+                values
+                    .into_iter()
+                    .map(|(key, value)| (key_json(&key), value_json(&value, unknown)))
+                    .collect(),
+            ),
+            _ => continue,
+        };
+        object.insert(field.json_name().to_string(), value);
+    }
+    if unknown {
+        if let Some(fields) = unknown_json(message) {
+            object.insert(UNKNOWN_FIELDS.to_string(), fields);
+        }
+    }
+    Value::Object(object)
+}
+
+/// 把消息转换为 JSON
+pub fn to_json(message: &dyn MessageDyn) -> Value {
+    message_json(message, false)
+}
+
+/// 同 `to_json`, 但是把解析时不认识的字段放在 `@unknown` 中, 用于调试.
+/// 结果不是标准的 proto3 JSON, 不要再用来解析
+pub fn to_json_with_unknown(message: &dyn MessageDyn) -> Value {
+    message_json(message, true)
+}
+
+/// 整数可以是 JSON 数字, 也可以是字符串
+fn parse_integer<T: TryFrom<i128>>(value: &Value) -> Result<T, JsonError> {
+    let integer = match value {
+        Value::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(integer), _, _) => Some(integer as i128),
+            (_, Some(integer), _) => Some(integer as i128),
+            (_, _, Some(float)) if float.fract() == 0.0 => Some(float as i128),
+            _ => None,
+        },
+        Value::String(text) => text.parse::<i128>().ok(),
+        _ => None,
+    };
+    match integer.map(T::try_from) {
+        Some(Ok(integer)) => Ok(integer),
+        _ => error(format!("expect integer, got {}", value)),
+    }
+}
+
+fn parse_float(value: &Value) -> Result<f64, JsonError> {
+    match value {
+        Value::Number(number) => Ok(number.as_f64().unwrap_or_default()),
+        Value::String(text) => match text.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            text => text
+                .parse()
+                .or_else(|_| error(format!("expect number, got {}", value))),
+        },
+        _ => error(format!("expect number, got {}", value)),
+    }
+}
+
+fn parse_bytes(value: &Value) -> Result<Vec<u8>, JsonError> {
+    let text = match value {
+        Value::String(text) => text,
+        _ => return error(format!("expect base64 string, got {}", value)),
+    };
+    // 标准和 url 安全的编码都可以, 补齐的 = 可以省略
+    let text = text.trim_end_matches('=');
+    base64::decode_config(text, base64::STANDARD_NO_PAD)
+        .or_else(|_| base64::decode_config(text, base64::URL_SAFE_NO_PAD))
+        .or_else(|error| self::error(format!("invalid base64: {}", error)))
+}
+
+fn parse_enum(descriptor: &EnumDescriptor, value: &Value) -> Result<i32, JsonError> {
+    match value {
+        Value::String(name) => match descriptor.value_by_name(name) {
+            Some(value) => Ok(value.value()),
+            None => error(format!("unknown {} value {}", descriptor.full_name(), name)),
+        },
+        Value::Null if descriptor.full_name() == NULL_VALUE => Ok(0),
+        value => parse_integer(value),
+    }
+}
+
+fn parse_value(runtime_type: &RuntimeType, value: &Value) -> Result<ReflectValueBox, JsonError> {
+    Ok(match runtime_type {
+        RuntimeType::I32 => ReflectValueBox::I32(parse_integer(value)?),
+        RuntimeType::I64 => ReflectValueBox::I64(parse_integer(value)?),
+        RuntimeType::U32 => ReflectValueBox::U32(parse_integer(value)?),
+        RuntimeType::U64 => ReflectValueBox::U64(parse_integer(value)?),
+        RuntimeType::F32 => ReflectValueBox::F32(parse_float(value)? as f32),
+        RuntimeType::F64 => ReflectValueBox::F64(parse_float(value)?),
+        RuntimeType::Bool => match value {
+            Value::Bool(value) => ReflectValueBox::Bool(*value),
+            _ => return error(format!("expect bool, got {}", value)),
+        },
+        RuntimeType::String => match value {
+            Value::String(value) => ReflectValueBox::String(value.clone()),
+            _ => return error(format!("expect string, got {}", value)),
+        },
+        RuntimeType::VecU8 => ReflectValueBox::Bytes(parse_bytes(value)?),
+        RuntimeType::Enum(descriptor) => {
+            ReflectValueBox::Enum(descriptor.clone(), parse_enum(descriptor, value)?)
+        }
+        RuntimeType::Message(descriptor) => {
+            let mut message = descriptor.new_instance();
+            merge_json(&mut *message, value)?;
+            ReflectValueBox::Message(message)
+        }
+    })
+}
+
+/// map 的 key 是字符串, 按 key 的类型解析其中的内容
+fn parse_key(runtime_type: &RuntimeType, key: &str) -> Result<ReflectValueBox, JsonError> {
+    match runtime_type {
+        RuntimeType::String => Ok(ReflectValueBox::String(key.to_string())),
+        RuntimeType::Bool => match key {
+            "true" => Ok(ReflectValueBox::Bool(true)),
+            "false" => Ok(ReflectValueBox::Bool(false)),
+            _ => error(format!("expect bool key, got {}", key)),
+        },
+        runtime_type => parse_value(runtime_type, &Value::String(key.to_string())),
+    }
+}
+
+fn merge_field(
+    message: &mut dyn MessageDyn,
+    field: &FieldDescriptor,
+    value: &Value,
+) -> Result<(), JsonError> {
+    match field.runtime_field_type() {
+        RuntimeFieldType::Singular(runtime_type) => {
+            let value = parse_value(&runtime_type, value)?;
+            field.set_singular_field(message, value);
+        }
+        RuntimeFieldType::Repeated(runtime_type) => {
+            let values = match value {
+                Value::Array(values) => values,
+                _ => return error(format!("expect array, got {}", value)),
+            };
+            let mut repeated = field.mut_repeated(message);
+            for value in values {
+                repeated.push(parse_value(&runtime_type, value)?);
+            }
+        }
+        RuntimeFieldType::Map(key_type, value_type) => {
+            let values = match value {
+                Value::Object(values) => values,
+                _ => return error(format!("expect object, got {}", value)),
+            };
+            let mut map = field.mut_map(message);
+            for (key, value) in values {
+                map.insert(parse_key(&key_type, key)?, parse_value(&value_type, value)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge_well_known(message: &mut dyn MessageDyn, value: &Value) -> Option<Result<(), JsonError>> {
+    let full_name = message.descriptor_dyn().full_name().to_string();
+    if is_wrapper(&full_name) {
+        let field = field(message, "value");
+        return Some(merge_field(message, &field, value));
+    }
+    let result = match full_name.as_str() {
+        "google.protobuf.Struct" => {
+            let field = field(message, "fields");
+            merge_field(message, &field, value)
+        }
+        "google.protobuf.ListValue" => {
+            let field = field(message, "values");
+            merge_field(message, &field, value)
+        }
+        "google.protobuf.Value" => {
+            let name = match value {
+                Value::Null => "null_value",
+                Value::Bool(_) => "bool_value",
+                Value::Number(_) => "number_value",
+                Value::String(_) => "string_value",
+                Value::Array(_) => "list_value",
+                Value::Object(_) => "struct_value",
+            };
+            let field = field(message, name);
+            merge_field(message, &field, value)
+        }
+        "google.protobuf.Timestamp" | "google.protobuf.Duration" => {
+            let parsed = match value {
+                Value::String(text) if full_name.ends_with("Timestamp") => parse_timestamp(text),
+                Value::String(text) => parse_duration(text),
+                _ => None,
+            };
+            match parsed {
+                Some((seconds, nanos)) => {
+                    field(message, "seconds").set_singular_field(message, seconds.into());
+                    field(message, "nanos").set_singular_field(message, nanos.into());
+                    Ok(())
+                }
+                None => error(format!("invalid {}: {}", full_name, value)),
+            }
+        }
+        "google.protobuf.FieldMask" => match value {
+            Value::String(paths) => {
+                let field = field(message, "paths");
+                let mut repeated = field.mut_repeated(message);
+                for path in paths.split(',').filter(|path| !path.is_empty()) {
+                    repeated.push(ReflectValueBox::String(snake(path)));
+                }
+                Ok(())
+            }
+            _ => error(format!("expect field mask string, got {}", value)),
+        },
+        "google.protobuf.Any" => merge_any(message, value),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn merge_any(message: &mut dyn MessageDyn, value: &Value) -> Result<(), JsonError> {
+    let object = match value {
+        Value::Object(object) => object,
+        _ => return error(format!("expect any object, got {}", value)),
+    };
+    let type_url = match object.get(ANY_TYPE) {
+        Some(Value::String(type_url)) => type_url.clone(),
+        _ => return error(format!("any without {}", ANY_TYPE)),
+    };
+    let descriptor = match pool().message(any_type_name(&type_url)) {
+        Some(descriptor) => descriptor,
+        None => return error(format!("unknown any type {}", type_url)),
+    };
+    let mut inner = descriptor.new_instance();
+    let mut fields = object.clone();
+    fields.remove(ANY_TYPE);
+    match merge_well_known(&mut *inner, fields.get("value").unwrap_or(&Value::Null)) {
+        Some(result) => result?,
+        None => merge_json(&mut *inner, &Value::Object(fields))?,
+    }
+    let bytes = inner.write_to_bytes_dyn()?;
+    field(message, "type_url").set_singular_field(message, ReflectValueBox::String(type_url));
+    field(message, "value").set_singular_field(message, ReflectValueBox::Bytes(bytes));
+    Ok(())
+}
+
+/// 把 JSON 合并到消息中, 已有的 repeated 和 map 字段会追加
+pub fn merge_json(message: &mut dyn MessageDyn, value: &Value) -> Result<(), JsonError> {
+    if let Some(result) = merge_well_known(message, value) {
+        return result;
+    }
+    let object = match value {
+        Value::Object(object) => object,
+        Value::Null => return Ok(()),
+        _ => {
+            return error(format!(
+                "expect {} object, got {}",
+                message.descriptor_dyn().full_name(),
+                value
+            ))
+        }
+    };
+    let descriptor = message.descriptor_dyn();
+    for (name, value) in object {
+        let field = match descriptor.field_by_name_or_json_name(name) {
+            Some(field) => field,
+            None => continue,
+        };
+        // null 表示默认值, 只有 google.protobuf.Value 中的 null 有意义
+        let keeps_null = matches!(
+            field.runtime_field_type(),
+            RuntimeFieldType::Singular(RuntimeType::Message(ref message))
+                if message.full_name() == "google.protobuf.Value"
+        );
+        if value.is_null() && !keeps_null {
+            field.clear_field(message);
+            continue;
+        }
+        merge_field(message, &field, value)
+            .map_err(|error| JsonError(format!("{}: {}", name, error.0)))?;
+    }
+    Ok(())
+}
+
+/// 从 JSON 解析消息
+pub fn from_json<M: MessageFull>(value: &Value) -> Result<M, JsonError> {
+    let mut message = M::new();
+    merge_json(&mut message, value)?;
+    Ok(message)
+}
+
+/// 为生成的类型实现 serde: 消息按 proto3 的 JSON 映射, 枚举为名称, 也可以从数字解析
+macro_rules! proto_serde {
+    (message $ty:path) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                to_json(self).serialize(serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = Value::deserialize(deserializer)?;
+                from_json(&value).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+    (enum $ty:path) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(protobuf::EnumFull::descriptor(self).name())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = Value::deserialize(deserializer)?;
+                let descriptor = <$ty as protobuf::EnumFull>::enum_descriptor();
+                parse_enum(&descriptor, &value)
+                    .ok()
+                    .and_then(<$ty as protobuf::Enum>::from_i32)
+                    .ok_or_else(|| {
+                        serde::de::Error::custom(format!(
+                            "invalid {} value {}",
+                            descriptor.full_name(),
+                            value
+                        ))
+                    })
+            }
+        }
+    };
+}
+
+// 由 build.rs 生成, 每个消息和枚举一行
+include!(concat!(env!("OUT_DIR"), "/serde.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_days_round_trip() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-719_468), (0, 3, 1));
+        for days in (-800_000..800_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0, 0), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(1_685_606_400, 123_000_000),
+            "2023-06-01T08:00:00.123Z"
+        );
+        assert_eq!(format_timestamp(-1, 1_000), "1969-12-31T23:59:59.000001Z");
+
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some((0, 0)));
+        assert_eq!(
+            parse_timestamp("2023-06-01T16:00:00.123+08:00"),
+            Some((1_685_606_400, 123_000_000))
+        );
+        assert_eq!(
+            parse_timestamp("2023-06-01t08:00:00.5z"),
+            Some((1_685_606_400, 500_000_000))
+        );
+        assert_eq!(parse_timestamp("1969-12-31T20:00:00-04:00"), Some((0, 0)));
+        for (seconds, nanos) in [(0, 0), (-62_135_596_800, 0), (253_402_300_799, 999_999_999)] {
+            let text = format_timestamp(seconds, nanos);
+            assert_eq!(parse_timestamp(&text), Some((seconds, nanos)), "{}", text);
+        }
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        for text in [
+            "",
+            "2023-06-01",
+            "2023-06-01 08:00:00Z",
+            "2023-13-01T08:00:00Z",
+            "2023-02-30T08:00:00Z",
+            "2023-06-01T24:00:00Z",
+            "2023-06-01T08:00:00",
+            "2023-06-01T08:00:00.Z",
+            "2023-06-01T08:00:00.1234567890Z",
+            "2023-06-01T08:00:00+0800",
+            "２023-06-01T08:00:00Z",
+        ] {
+            assert_eq!(parse_timestamp(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(1, 500_000_000), "1.500s");
+        assert_eq!(format_duration(0, -10_000_000), "-0.010s");
+        assert_eq!(format_duration(-3, 0), "-3s");
+        assert_eq!(parse_duration("1.5s"), Some((1, 500_000_000)));
+        assert_eq!(parse_duration("-0.010s"), Some((0, -10_000_000)));
+        assert_eq!(parse_duration("3s"), Some((3, 0)));
+        for text in ["", "s", "1", "1.s", "-s", "1.5ms", "+1s", "1e3s"] {
+            assert_eq!(parse_duration(text), None, "{}", text);
+        }
+        for (seconds, nanos) in [(0, 0), (5, 1), (-5, -999_999_999)] {
+            let text = format_duration(seconds, nanos);
+            assert_eq!(parse_duration(&text), Some((seconds, nanos)), "{}", text);
+        }
+    }
+
+    use crate::message::{Body, DataType, InfoType, Push, Request, Response, Status};
+    use protobuf::{EnumOrUnknown, Message};
+
+    #[test]
+    fn request_round_trip() {
+        let mut request = Request::new();
+        request.url = "/task/run".to_string();
+        request.sequence = "s1".to_string();
+        request.sendTime = 1.5;
+        request.sendTimeMs = 1_700_000_000_123;
+        let mut body = Body::new();
+        body.type_ = Some(EnumOrUnknown::new(DataType::Object));
+        body.payload = vec![0, 1, 255];
+        request.data = Some(body).into();
+        let value = to_json(&request);
+        // int64 为字符串, bytes 为 base64, 默认值的字段不输出
+        assert_eq!(
+            value,
+            json!({
+                "url": "/task/run",
+                "sequence": "s1",
+                "sendTime": 1.5,
+                "data": { "type": "Object", "payload": "AAH/" },
+                "sendTimeMs": "1700000000123",
+            })
+        );
+        assert_eq!(from_json::<Request>(&value).unwrap(), request);
+        assert_eq!(serde_json::to_value(&request).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Request>(value).unwrap(), request);
+    }
+
+    #[test]
+    fn response_status() {
+        let mut response = Response::new();
+        response.sequence = "s1".to_string();
+        response.status = Some(EnumOrUnknown::new(Status::NOT_FOUND));
+        response.receiveTimeMs = -1;
+        let value = to_json(&response);
+        assert_eq!(
+            value,
+            json!({ "sequence": "s1", "status": "NOT_FOUND", "receiveTimeMs": "-1" })
+        );
+        assert_eq!(from_json::<Response>(&value).unwrap(), response);
+        // 枚举也可以是数字, 整数也可以是数字
+        let parsed =
+            from_json::<Response>(&json!({ "sequence": "s1", "status": 404, "receiveTimeMs": -1 }))
+                .unwrap();
+        assert_eq!(parsed, response);
+        // optional 的枚举设置为默认值时也会输出
+        response.status = Some(EnumOrUnknown::new(Status::UNKNOW));
+        assert_eq!(to_json(&response)["status"], "UNKNOW");
+        // 没有 status 时为 None
+        let parsed = from_json::<Response>(&json!({ "sequence": "s1" })).unwrap();
+        assert_eq!(parsed.status, None);
+        assert_eq!(to_json(&parsed), json!({ "sequence": "s1" }));
+        assert!(from_json::<Response>(&json!({ "status": "GONE" })).is_err());
+    }
+
+    #[test]
+    fn push_round_trip() {
+        assert_eq!(to_json(&Push::new()), json!({}));
+        let mut push = Push::new();
+        push.event = "task/done".to_string();
+        push.status = Some(EnumOrUnknown::new(InfoType::WARN));
+        push.seq = u64::MAX;
+        push.ack = true;
+        let value = to_json(&push);
+        assert_eq!(
+            value,
+            json!({
+                "event": "task/done",
+                "status": "WARN",
+                "seq": "18446744073709551615",
+                "ack": true,
+            })
+        );
+        assert_eq!(from_json::<Push>(&value).unwrap(), push);
+        // JSON 中的未知字段直接忽略
+        let parsed = from_json::<Push>(&json!({
+            "event": "task/done",
+            "status": 2,
+            "seq": "18446744073709551615",
+            "ack": true,
+            "extra": { "nested": [1, 2] },
+        }))
+        .unwrap();
+        assert_eq!(parsed, push);
+        assert_eq!(serde_json::to_value(InfoType::WARN).unwrap(), json!("WARN"));
+        assert_eq!(
+            serde_json::from_value::<InfoType>(json!(2)).unwrap(),
+            InfoType::WARN
+        );
+        assert_eq!(
+            serde_json::from_value::<InfoType>(json!("ERROR")).unwrap(),
+            InfoType::ERROR
+        );
+        assert!(serde_json::from_value::<InfoType>(json!(7)).is_err());
+    }
+
+    #[test]
+    fn unknown_wire_fields() {
+        let mut push = Push::new();
+        push.event = "e".to_string();
+        let mut bytes = push.write_to_bytes().unwrap();
+        // 字段号 100 的 varint 5
+        bytes.extend_from_slice(&[0xa0, 0x06, 0x05]);
+        let parsed = Push::parse_from_bytes(&bytes).unwrap();
+        assert_eq!(to_json(&parsed), json!({ "event": "e" }));
+        assert_eq!(
+            to_json_with_unknown(&parsed),
+            json!({
+                "event": "e",
+                UNKNOWN_FIELDS: [{ "field": 100, "wire": "varint", "value": "5" }],
+            })
+        );
+    }
+}
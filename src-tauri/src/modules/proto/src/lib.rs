@@ -1,9 +1,22 @@
-use message::{Body, DataType, Push};
-use protobuf::SpecialFields;
-use serde::ser::SerializeStruct;
-use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub mod message;
+use descriptors::{pool, DescriptorError};
+use message::{Body, DataType, Push, Request, Response};
+use protobuf::MessageFull;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// 由 build.rs 从 protos/*.proto 生成, 每个文件一个模块
+include!(concat!(env!("OUT_DIR"), "/protos/mod.rs"));
+pub mod descriptors;
+pub mod json;
+pub mod service;
+/// proto 中 service 的客户端, 由 build.rs 生成
+pub mod services {
+    include!(concat!(env!("OUT_DIR"), "/services.rs"));
+}
+#[cfg(feature = "ts")]
+pub mod ts;
 
 pub enum MessageType {
     PUSH,
@@ -34,26 +47,72 @@ impl MessageType {
     }
 }
 
-impl serde::Serialize for Push {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::ser::Serializer
-    {
-        let mut state = serializer.serialize_struct("Push", 3)?;
-        state.serialize_field("event", &self.event)?;
-        state.serialize_field("status", &self.status.unwrap().value())?;
-        state.serialize_field("data", &self.data.json_value())?;
-        state.serialize_field("sendTime", &self.sendTime)?;
-        state.end()
-    }
+/// 旧版本 float 的 sendTime 小于这个值时是秒, 否则是毫秒
+const LEGACY_SECONDS_LIMIT: f64 = 100_000_000_000.0;
+
+/// 当前的 unix 毫秒
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// 兼容新旧两种发送时间, 都没有时为 0
+pub trait SendTime {
+    fn send_time_ms(&self) -> i64;
+}
+
+macro_rules! impl_send_time {
+    ($($ty:ty),*) => {
+        $(impl SendTime for $ty {
+            fn send_time_ms(&self) -> i64 {
+                if self.sendTimeMs != 0 {
+                    return self.sendTimeMs;
+                }
+                let send_time = self.sendTime as f64;
+                if send_time < LEGACY_SECONDS_LIMIT {
+                    (send_time * 1000.0) as i64
+                } else {
+                    send_time as i64
+                }
+            }
+        })*
+    };
 }
 
-impl serde::Serialize for Body {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::ser::Serializer
-    {
-        self.value.serialize(serializer)
+impl_send_time!(Request, Response, Push);
+
+/// Push 发往页面时的结构
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct PushPayload {
+    pub event: String,
+    /// `InfoType` 的值
+    pub status: i32,
+    pub data: Value,
+    /// 秒, 与旧版本一致, 带毫秒的小数部分
+    pub send_time: f64,
+    #[cfg_attr(feature = "ts", ts(type = "number"))]
+    pub seq: u64,
+    pub session: String,
+    pub id: String,
+    pub ack: bool,
+}
+
+impl From<&Push> for PushPayload {
+    fn from(push: &Push) -> Self {
+        Self {
+            event: push.event.clone(),
+            status: push.status.map(|status| status.value()).unwrap_or_default(),
+            data: push.data.json_value(),
+            send_time: push.send_time_ms() as f64 / 1000.0,
+            seq: push.seq,
+            session: push.session.clone(),
+            id: push.id.clone(),
+            ack: push.ack,
+        }
     }
 }
 
@@ -72,20 +131,65 @@ impl DataType {
 }
 
 impl Body {
+    /// payload 按 `messageType` 编码, 而不是 json 文本
+    pub fn is_message(&self) -> bool {
+        !self.messageType.is_empty()
+    }
+
+    /// payload 按运行时加载的描述解码, 结果带上 `@type`.
+    /// 类型未知或者解码失败时返回 `@error` 和 base64 编码的原始 `@payload`
+    pub fn message_json(&self) -> Value {
+        match pool().decode(&self.messageType, &self.payload) {
+            Ok(Value::Object(mut object)) => {
+                object.insert("@type".to_string(), Value::String(self.messageType.clone()));
+                Value::Object(object)
+            }
+            // 包装类型等 well-known 类型不是对象
+            Ok(value) => json!({ "@type": self.messageType, "value": value }),
+            Err(error) => json!({
+                "@type": self.messageType,
+                "@error": error.to_string(),
+                "@payload": base64::encode(&self.payload),
+            }),
+        }
+    }
+
+    /// 按 proto3 的 JSON 映射把 data 编码为 `message_type` 类型的 payload
+    pub fn from_message(message_type: &str, data: &Value) -> Result<Body, DescriptorError> {
+        Ok(Body {
+            messageType: message_type.trim_start_matches('.').to_string(),
+            payload: pool().encode(message_type, data)?,
+            ..Default::default()
+        })
+    }
+
+    /// 生成的消息直接编码为 payload
+    pub fn from_proto<M: MessageFull>(message: &M) -> Result<Body, protobuf::Error> {
+        Ok(Body {
+            messageType: M::descriptor().full_name().to_string(),
+            payload: message.write_to_bytes()?,
+            ..Default::default()
+        })
+    }
+
+    /// 解码后的数据, 所有地方都通过它把 Body 转成 JSON.
+    /// 没有 Body 的 `MessageField` 解引用为默认值, 结果为 Null
     pub fn json_value(&self) -> Value {
-        match self.type_ {
-            Some(type_) => {
-                match type_.unwrap() {
-                    DataType::Bool => Value::Bool(serde_json::from_str(&self.value).unwrap()),
-                    DataType::String => Value::String(self.value.to_string()),
-                    DataType::Number => Value::Number(serde_json::from_str(&self.value).unwrap()),
-                    DataType::Array => Value::Array(serde_json::from_str(&self.value).unwrap()),
-                    DataType::Object => Value::Object(serde_json::from_str(&self.value).unwrap()),
-                    DataType::Null => Value::Null,
-                }
+        if self.is_message() {
+            return self.message_json();
+        }
+        let parsed = serde_json::from_str::<Value>(&self.value);
+        match self.type_.map(|type_| type_.enum_value()) {
+            Some(Ok(DataType::Null)) => Value::Null,
+            // 服务端发来的字符串可能没有引号, 此时按原文处理
+            Some(Ok(DataType::String)) => match parsed {
+                Ok(Value::String(value)) => Value::String(value),
+                _ => Value::String(self.value.clone()),
             },
-            None => Value::Null,
-        } 
+            _ if self.value.is_empty() => Value::Null,
+            // 数据与类型不符时保留原始字符串, 不能 panic
+            _ => parsed.unwrap_or_else(|_| Value::String(self.value.clone())),
+        }
     }
 }
 
@@ -106,8 +210,47 @@ impl MessageBody for Body {
         Body {
             type_: Some(type_.into()),
             value: serde_json::to_string(&data).unwrap(),
-            special_fields: SpecialFields::default(),
+            ..Default::default()
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use protobuf::MessageField;
+    use serde_json::json;
+
+    use super::*;
+
+    fn body(type_: Option<DataType>, value: &str) -> Body {
+        Body {
+            type_: type_.map(|type_| type_.into()),
+            value: value.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn json_value_round_trips_serialized_data() {
+        for data in [json!("boom"), json!(1.5), json!(true), json!([1, "a"]), json!({ "id": 1 })] {
+            assert_eq!(Body::from_serialize(data.clone()).json_value(), data);
+        }
+    }
+
+    #[test]
+    fn json_value_accepts_unquoted_strings() {
+        assert_eq!(body(Some(DataType::String), "\"boom\"").json_value(), json!("boom"));
+        assert_eq!(body(Some(DataType::String), "boom").json_value(), json!("boom"));
+        assert_eq!(body(Some(DataType::String), "123").json_value(), json!("123"));
+    }
+
+    #[test]
+    fn json_value_never_panics() {
+        assert_eq!(body(Some(DataType::Object), "{broken").json_value(), json!("{broken"));
+        assert_eq!(body(Some(DataType::Null), "anything").json_value(), Value::Null);
+        assert_eq!(body(None, "").json_value(), Value::Null);
+        assert_eq!(body(None, "[1]").json_value(), json!([1]));
+        assert_eq!(MessageField::<Body>::none().json_value(), Value::Null);
+    }
+}
@@ -0,0 +1,73 @@
+//! proto 中 service 的客户端运行时, 各个 service 的客户端由 build.rs 生成到 `services` 模块
+
+use std::{future::Future, pin::Pin};
+
+use protobuf::{MessageField, MessageFull};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    json::{from_json, to_json, JsonError},
+    message::{Body, Response},
+    MessageBody,
+};
+
+pub type RpcFuture<'a> = Pin<Box<dyn Future<Output = Result<Response, Response>> + Send + 'a>>;
+
+/// 按 url 发送请求并等待响应的连接. 没有状态码的错误响应表示请求没有到达服务端 (超时或发送失败)
+pub trait RpcTransport {
+    fn call(&mut self, url: &str, body: Body) -> RpcFuture<'_>;
+}
+
+#[derive(Debug, Error)]
+pub enum RpcError {
+    /// 服务端返回了非 OK 的状态
+    #[error("server returned status {status}")]
+    Server { status: i32, data: Value },
+    /// 超时或发送失败, 请求不一定到达了服务端
+    #[error("{0}")]
+    Local(String),
+    #[error("decode response error: {0}")]
+    Decode(JsonError),
+}
+
+/// 响应的 payload 就是 Resp 时直接解码, 否则按 JSON 解析
+fn decode_response<Resp: MessageFull>(body: &MessageField<Body>) -> Result<Resp, RpcError> {
+    match body.as_ref() {
+        Some(body) if body.messageType == Resp::descriptor().full_name() => {
+            Resp::parse_from_bytes(&body.payload)
+                .map_err(|error| RpcError::Decode(JsonError::from(error)))
+        }
+        _ => from_json(&body.json_value()).map_err(RpcError::Decode),
+    }
+}
+
+/// 生成的客户端调用的方法, 请求的消息按 proto3 的 JSON 映射放在 Body 中,
+/// 响应可以是 JSON, 也可以是 payload 为 Resp 的编码
+pub async fn call<T, Req, Resp>(
+    transport: &mut T,
+    url: &str,
+    request: &Req,
+) -> Result<Resp, RpcError>
+where
+    T: RpcTransport + ?Sized,
+    Req: MessageFull,
+    Resp: MessageFull,
+{
+    match transport
+        .call(url, Body::from_serialize(to_json(request)))
+        .await
+    {
+        Ok(response) => decode_response(&response.data),
+        Err(response) => match response.status {
+            Some(status) => Err(RpcError::Server {
+                status: status.value(),
+                data: response.data.json_value(),
+            }),
+            None => Err(RpcError::Local(match response.data.json_value() {
+                Value::String(error) => error,
+                error => error.to_string(),
+            })),
+        },
+    }
+}
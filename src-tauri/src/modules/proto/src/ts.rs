@@ -0,0 +1,15 @@
+use protobuf::EnumFull;
+
+/// 把 proto 枚举生成 TypeScript 常量和对应的数字类型, 页面按名字比较推送的状态
+pub fn enum_const<E: EnumFull>() -> String {
+    let values = E::VALUES
+        .iter()
+        .map(|value| format!("{}: {}", value.descriptor().name(), value.value()))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!(
+        "export const {name} = {{ {values} }} as const;\nexport type {name} = (typeof {name})[keyof typeof {name}];",
+        name = E::NAME,
+        values = values
+    )
+}
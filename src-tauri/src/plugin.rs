@@ -1 +1,5 @@
-pub mod connect;
\ No newline at end of file
+#[cfg(feature = "bindings")]
+pub mod bindings;
+pub mod connect;
+pub mod handle;
+pub mod sink;
@@ -0,0 +1,532 @@
+//! 页面使用的 TypeScript 绑定, 命令, 事件名和 payload 的类型都从这里的 Rust 定义生成.
+//! 修改命令或者类型后运行 `npm run bindings` 重新生成 `src/utils/client/bindings.ts`
+
+use std::collections::HashMap;
+
+use client::{
+    clock::ClockSnapshot,
+    emitter::ClientScope,
+    inspector::{InspectorEntry, InspectorFilter},
+    metrics::{ConnectionSnapshot, LatencySnapshot, UrlSnapshot},
+    push_policy::{PushPolicy, PushStats},
+    recorder::RecordOptions,
+    replay::{ReplayEvent, ReplayResult},
+    sequence::ResyncNotice,
+    store::{CollectionSpec, StoreFilter, StoreSubscription, StoreView},
+    traffic::Direction,
+    utils::{
+        CLIENT_IDENTIFICATION, CLIENT_IDENTIFICATION_CLOSE, CLIENT_IDENTIFICATION_CONNECT_ERROR,
+        CLIENT_IDENTIFICATION_ERROR, CLIENT_IDENTIFICATION_INSPECTOR, CLIENT_IDENTIFICATION_PUSH,
+        CLIENT_IDENTIFICATION_REQUEST, CLIENT_IDENTIFICATION_RESPONSE,
+        CLIENT_IDENTIFICATION_RESYNC, CLIENT_IDENTIFICATION_STORE,
+    },
+    w_client::ClientInfo,
+};
+use proto::{message::InfoType, ts::enum_const, PushPayload};
+use serde_json::Value;
+use ts_rs::TS;
+
+use super::connect::LResponse;
+
+const PLUGIN: &str = "connect";
+
+const HEADER: &str = "// 由 src-tauri/src/plugin/bindings.rs 生成, 不要手动修改, 运行 `npm run bindings` 更新
+
+import { invoke } from \"@tauri-apps/api\";
+
+export type JsonValue = number | string | boolean | Array<JsonValue> | { [key in string]?: JsonValue } | null;";
+
+/// 没有返回数据的命令
+const NONE: &str = "null";
+
+struct Arg {
+    name: &'static str,
+    ty: String,
+    /// Rust 类型, 用来和命令函数的参数比较
+    rust: &'static str,
+    optional: bool,
+}
+
+fn arg<T: TS>(name: &'static str) -> Arg {
+    Arg {
+        name,
+        ty: T::name(),
+        rust: std::any::type_name::<T>(),
+        optional: false,
+    }
+}
+
+fn opt<T: TS>(name: &'static str) -> Arg {
+    Arg {
+        name,
+        ty: T::name(),
+        rust: std::any::type_name::<T>(),
+        optional: true,
+    }
+}
+
+struct Command {
+    name: &'static str,
+    args: Vec<Arg>,
+    /// 成功时 `LResponse.data` 的类型
+    data: String,
+}
+
+fn command(name: &'static str, args: Vec<Arg>, data: String) -> Command {
+    Command { name, args, data }
+}
+
+/// 插件的命令, 与 `generate_handler!` 中注册的命令一一对应.
+/// 参数名为 Rust 参数名的 camelCase, tauri 按它匹配. 参数由 `check_commands` 与 connect.rs 比较
+fn commands() -> Vec<Command> {
+    vec![
+        command(
+            "connect",
+            vec![
+                arg::<String>("ip"),
+                arg::<u16>("port"),
+                opt::<String>("name"),
+                opt::<ClientScope>("scope"),
+                opt::<String>("page"),
+            ],
+            String::name(),
+        ),
+        command("disconnect", vec![arg::<String>("id")], NONE.into()),
+        command("list_clients", vec![], Vec::<ClientInfo>::name()),
+        command(
+            "attach_window",
+            vec![arg::<String>("id"), opt::<String>("label")],
+            bool::name(),
+        ),
+        command(
+            "detach_window",
+            vec![arg::<String>("id"), opt::<String>("label")],
+            NONE.into(),
+        ),
+        command(
+            "send",
+            vec![
+                arg::<String>("id"),
+                arg::<Value>("data"),
+                arg::<String>("url"),
+                opt::<String>("proto"),
+            ],
+            Value::name(),
+        ),
+        command(
+            "start_record",
+            vec![opt::<String>("dir"), opt::<RecordOptions>("options")],
+            String::name(),
+        ),
+        command("stop_record", vec![], NONE.into()),
+        command(
+            "replay",
+            vec![
+                arg::<String>("id"),
+                arg::<String>("path"),
+                opt::<bool>("realtime"),
+            ],
+            ReplayResult::name(),
+        ),
+        command(
+            "inspector_query",
+            vec![opt::<InspectorFilter>("filter")],
+            Vec::<InspectorEntry>::name(),
+        ),
+        command(
+            "inspector_clear",
+            vec![opt::<String>("address")],
+            NONE.into(),
+        ),
+        command(
+            "inspector_config",
+            vec![opt::<bool>("enabled"), opt::<usize>("capacity")],
+            NONE.into(),
+        ),
+        command("open_inspector", vec![], NONE.into()),
+        command(
+            "load_descriptors",
+            vec![arg::<String>("path")],
+            Vec::<String>::name(),
+        ),
+        command("message_types", vec![], Vec::<String>::name()),
+        command(
+            "metrics",
+            vec![opt::<String>("export"), opt::<bool>("reset")],
+            HashMap::<String, ConnectionSnapshot>::name(),
+        ),
+        command(
+            "push_policy",
+            vec![
+                arg::<String>("id"),
+                arg::<String>("event"),
+                opt::<PushPolicy>("policy"),
+            ],
+            HashMap::<String, PushStats>::name(),
+        ),
+        command(
+            "push_stats",
+            vec![arg::<String>("id")],
+            format!(
+                "{{ policies: {}, stats: {} }}",
+                HashMap::<String, PushPolicy>::name(),
+                HashMap::<String, PushStats>::name()
+            ),
+        ),
+        command(
+            "push_ack",
+            vec![arg::<String>("id"), arg::<Vec<String>>("ids")],
+            usize::name(),
+        ),
+        command("push_redeliver", vec![arg::<String>("id")], usize::name()),
+        command(
+            "store_register",
+            vec![arg::<String>("id"), arg::<CollectionSpec>("spec")],
+            NONE.into(),
+        ),
+        command(
+            "store_query",
+            vec![
+                arg::<String>("id"),
+                arg::<String>("collection"),
+                opt::<StoreFilter>("filter"),
+            ],
+            StoreView::name(),
+        ),
+        command(
+            "store_subscribe",
+            vec![
+                arg::<String>("id"),
+                arg::<String>("collection"),
+                opt::<StoreFilter>("filter"),
+            ],
+            StoreSubscription::name(),
+        ),
+        command(
+            "store_unsubscribe",
+            vec![arg::<String>("id"), arg::<String>("subscription")],
+            bool::name(),
+        ),
+        command(
+            "store_resync",
+            vec![arg::<String>("id"), arg::<String>("collection")],
+            NONE.into(),
+        ),
+    ]
+}
+
+/// 发往页面的事件和 payload 的类型
+fn events() -> Vec<(&'static str, String)> {
+    vec![
+        (CLIENT_IDENTIFICATION_RESPONSE, String::name()),
+        (CLIENT_IDENTIFICATION_PUSH, PushPayload::name()),
+        (CLIENT_IDENTIFICATION_REQUEST, String::name()),
+        (CLIENT_IDENTIFICATION_CLOSE, String::name()),
+        (CLIENT_IDENTIFICATION_ERROR, String::name()),
+        (CLIENT_IDENTIFICATION_CONNECT_ERROR, String::name()),
+        (CLIENT_IDENTIFICATION_INSPECTOR, InspectorEntry::name()),
+        // 订阅的视图会带上订阅 id
+        (
+            CLIENT_IDENTIFICATION_STORE,
+            format!("{} & {{ subscription: string }}", StoreView::name()),
+        ),
+        (CLIENT_IDENTIFICATION_RESYNC, ResyncNotice::name()),
+    ]
+}
+
+fn decl<T: TS>() -> String {
+    format!("{}export {}", T::docs().unwrap_or_default(), T::decl())
+}
+
+fn declarations() -> Vec<String> {
+    vec![
+        decl::<LResponse>(),
+        decl::<PushPayload>(),
+        enum_const::<InfoType>(),
+        decl::<ClientScope>(),
+        decl::<ClientInfo>(),
+        decl::<PushPolicy>(),
+        decl::<PushStats>(),
+        decl::<CollectionSpec>(),
+        decl::<StoreFilter>(),
+        decl::<StoreView>(),
+        decl::<StoreSubscription>(),
+        decl::<ResyncNotice>(),
+        decl::<RecordOptions>(),
+        decl::<ReplayEvent>(),
+        decl::<ReplayResult>(),
+        decl::<Direction>(),
+        decl::<InspectorFilter>(),
+        decl::<InspectorEntry>(),
+        decl::<LatencySnapshot>(),
+        decl::<UrlSnapshot>(),
+        decl::<ClockSnapshot>(),
+        decl::<ConnectionSnapshot>(),
+    ]
+}
+
+fn events_ts() -> String {
+    let mut lines = vec![format!(
+        "export const {name} = \"{name}\";",
+        name = CLIENT_IDENTIFICATION
+    )];
+    let events = events();
+    for (name, _) in events.iter() {
+        lines.push(format!("export const {name} = \"{name}\";", name = name));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "export const formatEventName = (name: string) => `${{{}}}::${{name}}`;",
+        CLIENT_IDENTIFICATION
+    ));
+    lines.push(String::new());
+    lines.push("export type EventPayloads = {".to_string());
+    for (name, payload) in events.iter() {
+        lines.push(format!("    [{}]: {},", name, payload));
+    }
+    lines.push("};".to_string());
+    lines.join("\n")
+}
+
+fn commands_ts() -> String {
+    let mut lines = vec!["export type Commands = {".to_string()];
+    for command in commands() {
+        let args = command
+            .args
+            .iter()
+            .map(|arg| {
+                format!(
+                    "{}{}: {}",
+                    arg.name,
+                    if arg.optional { "?" } else { "" },
+                    arg.ty
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let args = if args.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{ {} }}", args)
+        };
+        lines.push(format!(
+            "    {}: {{ args: {}, data: {} }},",
+            command.name, args, command.data
+        ));
+    }
+    lines.push("};".to_string());
+    lines.push(
+        "
+export type CommandArgs<K extends keyof Commands> = Commands[K][\"args\"];
+
+export type CommandResponse<T> = Omit<LResponse, \"data\"> & { data: T };
+
+/**
+ * 调用插件命令, 返回 json 的命令可以用 D 指定 data 的具体类型.
+ * 失败时 reject 的是 data 为错误信息的 `LResponse`
+ */
+export const invokeCommand = <K extends keyof Commands, D = Commands[K][\"data\"]>(
+    command: K,
+    ...args: {} extends CommandArgs<K> ? [args?: CommandArgs<K>] : [args: CommandArgs<K>]
+): Promise<CommandResponse<D>> => invoke(`plugin:PLUGIN|${command}`, args[0]);"
+            .replace("PLUGIN", PLUGIN),
+    );
+    lines.join("\n")
+}
+
+/// connect.rs 的源码, 检查命令表是否与命令函数一致
+const CONNECT_SOURCE: &str = include_str!("connect.rs");
+
+/// tauri 注入的参数, 不由页面传入
+const INJECTED: [&str; 3] = ["Window<", "State<", "AppHandle<"];
+
+/// 去掉类型中的路径, 例如 `alloc::vec::Vec<alloc::string::String>` 为 `Vec<String>`
+fn short_type(ty: &str) -> String {
+    let mut short = String::new();
+    let mut word = String::new();
+    for ch in ty.chars().filter(|ch| !ch.is_whitespace()) {
+        if ch.is_alphanumeric() || ch == '_' || ch == ':' {
+            word.push(ch);
+            continue;
+        }
+        short.push_str(word.rsplit("::").next().unwrap_or_default());
+        word.clear();
+        short.push(ch);
+    }
+    short.push_str(word.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+fn camel(name: &str) -> String {
+    let mut words = name.split('_');
+    let mut camel = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+/// 按最外层的逗号切分参数列表
+fn split_params(params: &str) -> Vec<&str> {
+    let (mut parts, mut depth, mut start) = (vec![], 0, 0);
+    for (index, ch) in params.char_indices() {
+        match ch {
+            '<' | '(' => depth += 1,
+            '>' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&params[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&params[start..]);
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// 页面传入的参数 `(名称, 类型, 是否可选)`
+type SourceArg = (String, String, bool);
+
+/// connect.rs 中 `#[tauri::command]` 函数的名称和页面传入的参数
+fn source_commands() -> Vec<(String, Vec<SourceArg>)> {
+    let mut commands = vec![];
+    for source in CONNECT_SOURCE.split("#[tauri::command]").skip(1) {
+        let source = match source.split_once("fn ") {
+            Some((_, source)) => source,
+            None => continue,
+        };
+        let name_end = source.find(['<', '(']).unwrap_or_default();
+        let params_start = match source.find('(') {
+            Some(start) => start + 1,
+            None => continue,
+        };
+        let params_end = source.find(") ->").unwrap_or(source.len());
+        let args = split_params(&source[params_start..params_end])
+            .into_iter()
+            .filter_map(|param| {
+                let (name, ty) = param.split_once(':')?;
+                let ty = ty.trim();
+                if INJECTED.iter().any(|injected| ty.starts_with(injected)) {
+                    return None;
+                }
+                let ty = if ty == "&str" { "String" } else { ty };
+                Some(match ty.strip_prefix("Option<") {
+                    Some(inner) => (
+                        camel(name.trim()),
+                        short_type(&inner[..inner.len() - 1]),
+                        true,
+                    ),
+                    None => (camel(name.trim()), short_type(ty), false),
+                })
+            })
+            .collect();
+        commands.push((source[..name_end].trim().to_string(), args));
+    }
+    commands
+}
+
+/// `generate_handler!` 中注册的命令
+fn registered_commands() -> Vec<String> {
+    let list = CONNECT_SOURCE
+        .split("generate_handler![")
+        .nth(1)
+        .and_then(|rest| rest.split(']').next())
+        .unwrap_or_default();
+    split_params(list).into_iter().map(str::to_string).collect()
+}
+
+/// 检查 `commands()` 与 connect.rs 中的命令函数和注册的命令一致, 返回所有不一致的地方
+pub fn check_commands() -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    let declared = commands();
+    let names: Vec<&str> = declared.iter().map(|command| command.name).collect();
+    let registered = registered_commands();
+    if names != registered {
+        errors.push(format!(
+            "commands() {:?} != generate_handler! {:?}",
+            names, registered
+        ));
+    }
+    let sources = source_commands();
+    for (name, args) in sources.iter() {
+        let command = match declared.iter().find(|command| command.name == name) {
+            Some(command) => command,
+            None => {
+                errors.push(format!("command {} missing in commands()", name));
+                continue;
+            }
+        };
+        let expected: Vec<SourceArg> = command
+            .args
+            .iter()
+            .map(|arg| (arg.name.to_string(), short_type(arg.rust), arg.optional))
+            .collect();
+        if &expected != args {
+            errors.push(format!(
+                "command {} args {:?} != connect.rs {:?}",
+                name, expected, args
+            ));
+        }
+    }
+    for command in declared.iter() {
+        if !sources.iter().any(|(name, _)| name == command.name) {
+            errors.push(format!("command {} has no #[tauri::command]", command.name));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// 生成完整的 bindings.ts 内容
+pub fn typescript() -> String {
+    let mut parts = vec![HEADER.to_string(), events_ts()];
+    parts.extend(declarations());
+    parts.push(commands_ts());
+    format!("{}\n", parts.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_are_fresh() {
+        // 不一致时运行 `cargo run --features bindings --bin bindings` 重新生成
+        let generated = include_str!("../../../src/utils/client/bindings.ts");
+        assert!(
+            typescript() == generated.replace("\r\n", "\n"),
+            "src/utils/client/bindings.ts is stale"
+        );
+    }
+
+    #[test]
+    fn commands_match_handlers() {
+        if let Err(errors) = check_commands() {
+            panic!("{}", errors.join("\n"));
+        }
+    }
+
+    #[test]
+    fn short_type_names() {
+        assert_eq!(
+            short_type(std::any::type_name::<Vec<String>>()),
+            "Vec<String>"
+        );
+        assert_eq!(
+            short_type("Option<client::store::StoreFilter>"),
+            "Option<StoreFilter>"
+        );
+        assert_eq!(camel("store_id"), "storeId");
+    }
+}
@@ -1,14 +1,43 @@
 use anyhow::Result;
 use proto::{message::Body, MessageBody};
-use serde_json::Value;
-use std::fmt::Debug;
-use tauri::{plugin::TauriPlugin, Manager, RunEvent, Runtime, State, Window};
-use tokio::sync::Mutex;
+use serde_json::{json, Value};
+use std::{
+    ffi::OsStr,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tauri::{
+    plugin::TauriPlugin, AppHandle, Manager, RunEvent, Runtime, State, Window, WindowBuilder,
+    WindowEvent, WindowUrl,
+};
+use tokio::sync::broadcast;
 
-use crate::client::client_manage::ClientManage;
+use client::{
+    client_manage::ClientManage,
+    ctx_info,
+    emitter::ClientScope,
+    inbound::InboundConfig,
+    inspector::InspectorFilter,
+    logger::LogContext,
+    push_policy::PushPolicy,
+    recorder::RecordOptions,
+    replay,
+    store::{CollectionSpec, StoreAction, StoreFilter},
+    utils::{CLIENT_IDENTIFICATION, CLIENT_IDENTIFICATION_CLOSE, CLIENT_IDENTIFICATION_INSPECTOR},
+    w_client::ClientOwner,
+    wrap_event_err,
+};
+use log::error;
 
+use super::{handle::ConnectHandle, sink::WindowSink};
+
+const INSPECTOR_WINDOW_LABEL: &str = "inspector";
+
+/// 命令的返回值, 成功和失败都是这个结构, 失败时 code 不为 0
 #[derive(Debug, serde::Serialize, Default)]
-struct LResponse {
+#[cfg_attr(feature = "bindings", derive(ts_rs::TS))]
+pub(super) struct LResponse {
     code: u32,
     data: Value,
 }
@@ -33,14 +62,22 @@ impl LResponse {
 async fn connect<R: Runtime>(
     ip: String,
     port: u16,
+    name: Option<String>,
+    scope: Option<ClientScope>,
+    page: Option<String>,
     win: Window<R>,
     c_manage: State<'_, ClientState<R>>,
 ) -> Result<LResponse, LResponse> {
     let res = c_manage
         .client_manage
-        .lock()
-        .await
-        .add_client(win, ip, port)
+        .add_client(
+            c_manage.sink.clone(),
+            ClientOwner::new(win.label()).page(page),
+            ip,
+            port,
+            name,
+            scope.unwrap_or_default(),
+        )
         .await;
     match res {
         Ok(id) => Ok(LResponse::default().data(Value::String(id))),
@@ -50,14 +87,62 @@ async fn connect<R: Runtime>(
     }
 }
 
+/// 当前窗口的所有客户端
+#[tauri::command]
+async fn list_clients<R: Runtime>(
+    win: Window<R>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let clients = c_manage.client_manage.list_clients(win.label());
+    Ok(LResponse::default().data(serde_json::to_value(clients).unwrap_or_default()))
+}
+
+/// 让窗口 (默认是调用的窗口) 接收已有客户端的事件, 之后也可以用这个 id 发送请求
+#[tauri::command]
+async fn attach_window<R: Runtime>(
+    id: String,
+    label: Option<String>,
+    win: Window<R>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let label = label.unwrap_or_else(|| win.label().to_string());
+    match c_manage.client_manage.attach_window(id, &label) {
+        Ok(attached) => Ok(LResponse::default().data(Value::Bool(attached))),
+        Err(err) => Err(LResponse::default()
+            .code(1)
+            .data(Value::String(err.to_string()))),
+    }
+}
+
+/// 窗口不再接收客户端的事件, 没有窗口接收时客户端会被移除
+#[tauri::command]
+async fn detach_window<R: Runtime>(
+    id: String,
+    label: Option<String>,
+    win: Window<R>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let label = label.unwrap_or_else(|| win.label().to_string());
+    match c_manage.client_manage.detach_window(id, &label) {
+        Ok(_) => Ok(LResponse::default()),
+        Err(err) => Err(LResponse::default()
+            .code(1)
+            .data(Value::String(err.to_string()))),
+    }
+}
+
 #[tauri::command]
 async fn disconnect<R: Runtime>(
     id: String,
     win: Window<R>,
     c_manage: State<'_, ClientState<R>>,
 ) -> Result<LResponse, LResponse> {
-    println!("disconnect: {}", id);
-    let res = c_manage.client_manage.lock().await.remove_client(&win, id);
+    ctx_info!(
+        LogContext::new().client_id(&id).window(win.label()),
+        "disconnect"
+    );
+    let res = c_manage.client_manage.remove_client(id);
+    wrap_event_err!(win, CLIENT_IDENTIFICATION_CLOSE, "close");
     match res {
         Ok(_) => Ok(LResponse::default()),
         Err(err) => Err(LResponse::default()
@@ -71,17 +156,22 @@ async fn send<'a, R: Runtime>(
     id: &str,
     data: Value,
     url: String,
+    proto: Option<String>,
     win: Window<R>,
     c_manage: State<'_, ClientState<R>>,
 ) -> Result<LResponse, LResponse> {
-    let client = c_manage
-        .client_manage
-        .lock()
-        .await
-        .get_client(id.to_string());
+    let client = c_manage.client_manage.get_client(id.to_string());
     match client {
         Some(mut client) => {
-            let body = Body::from_serialize(data);
+            // 指定了消息类型时按 proto 编码, 否则以 json 发送
+            let body = match proto {
+                Some(proto) => Body::from_message(&proto, &data).map_err(|err| {
+                    LResponse::default()
+                        .code(1)
+                        .data(Value::String(err.to_string()))
+                })?,
+                None => Body::from_serialize(data),
+            };
             match client.request(url, body).await {
                 Ok(res) => Ok(LResponse::default().data(res)),
                 Err(res) => Err(LResponse::default().code(1).data(res)),
@@ -93,37 +183,488 @@ async fn send<'a, R: Runtime>(
     }
 }
 
-pub struct ClientState<R: Runtime> {
-    client_manage: Mutex<ClientManage<R>>,
+#[tauri::command]
+async fn start_record<R: Runtime>(
+    dir: Option<String>,
+    options: Option<RecordOptions>,
+    app: AppHandle<R>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let dir = match dir.map(PathBuf::from).or_else(|| {
+        app.path_resolver()
+            .app_log_dir()
+            .map(|dir| dir.join("traffic"))
+    }) {
+        Some(dir) => dir,
+        None => {
+            return Err(LResponse::default()
+                .code(1)
+                .data(Value::String("record dir not found".to_string())))
+        }
+    };
+    let res = c_manage
+        .client_manage
+        .start_record(dir, options.unwrap_or_default());
+    match res {
+        Ok(path) => Ok(LResponse::default().data(Value::String(path.display().to_string()))),
+        Err(err) => Err(LResponse::default()
+            .code(1)
+            .data(Value::String(err.to_string()))),
+    }
 }
 
-pub struct Builder {}
+#[tauri::command]
+async fn stop_record<R: Runtime>(
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    c_manage.client_manage.stop_record();
+    Ok(LResponse::default())
+}
 
-impl Default for Builder {
-    fn default() -> Self {
-        Self {}
+#[tauri::command]
+async fn replay<R: Runtime>(
+    id: String,
+    path: String,
+    realtime: Option<bool>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let client = c_manage.client_manage.get_client(id);
+    let client = match client {
+        Some(client) => client,
+        None => {
+            return Err(LResponse::default()
+                .code(1)
+                .data(Value::String("not found client".to_string())))
+        }
+    };
+    let res = match replay::load(path) {
+        Ok(records) => replay::replay(&client, records, realtime.unwrap_or(false)).await,
+        Err(err) => Err(err),
+    };
+    match res {
+        Ok(result) => Ok(LResponse::default().data(json!(result))),
+        Err(err) => Err(LResponse::default()
+            .code(1)
+            .data(Value::String(err.to_string()))),
     }
 }
 
+#[tauri::command]
+async fn inspector_query<R: Runtime>(
+    filter: Option<InspectorFilter>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let entries = c_manage
+        .client_manage
+        .inspector_query(&filter.unwrap_or_default());
+    match serde_json::to_value(entries) {
+        Ok(data) => Ok(LResponse::default().data(data)),
+        Err(err) => Err(LResponse::default()
+            .code(1)
+            .data(Value::String(err.to_string()))),
+    }
+}
+
+#[tauri::command]
+async fn inspector_clear<R: Runtime>(
+    address: Option<String>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    c_manage.client_manage.inspector_clear(address.as_deref());
+    Ok(LResponse::default())
+}
+
+#[tauri::command]
+async fn inspector_config<R: Runtime>(
+    enabled: Option<bool>,
+    capacity: Option<usize>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    c_manage
+        .client_manage
+        .inspector_configure(enabled, capacity);
+    Ok(LResponse::default())
+}
+
+/// 打开检查器窗口, 并把新的记录推送到该窗口, 窗口关闭后停止推送
+#[tauri::command]
+async fn open_inspector<R: Runtime>(
+    app: AppHandle<R>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    if let Some(window) = app.get_window(INSPECTOR_WINDOW_LABEL) {
+        let _ = window.set_focus();
+        return Ok(LResponse::default());
+    }
+    let window = WindowBuilder::new(
+        &app,
+        INSPECTOR_WINDOW_LABEL,
+        WindowUrl::App(INSPECTOR_WINDOW_LABEL.into()),
+    )
+    .title("Protocol Inspector")
+    .inner_size(1000.0, 700.0)
+    .build();
+    let window = match window {
+        Ok(window) => window,
+        Err(err) => {
+            return Err(LResponse::default()
+                .code(1)
+                .data(Value::String(err.to_string())))
+        }
+    };
+
+    let mut receiver = c_manage.client_manage.inspector_subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(entry) => {
+                    if app.get_window(INSPECTOR_WINDOW_LABEL).is_none() {
+                        break;
+                    }
+                    wrap_event_err!(window, CLIENT_IDENTIFICATION_INSPECTOR, entry);
+                }
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    error!("inspector lagged {} entries", count);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    Ok(LResponse::default())
+}
+
+/// 加载 FileDescriptorSet 文件, 返回其中的消息类型
+#[tauri::command]
+async fn load_descriptors<R: Runtime>(
+    path: String,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.load_descriptors(&path) {
+        Ok(names) => Ok(LResponse::default().data(json!(names))),
+        Err(err) => Err(LResponse::default()
+            .code(1)
+            .data(Value::String(err.to_string()))),
+    }
+}
+
+/// 运行时加载的消息类型, 包括从服务端获取的
+#[tauri::command]
+async fn message_types<R: Runtime>(
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    Ok(LResponse::default().data(json!(c_manage.client_manage.message_types())))
+}
+
+/// 返回统计快照, `export` 不为空时同时以 Prometheus 文本格式写入日志目录下的
+/// `metrics/<export>`, `export` 只能是文件名. `reset` 为 true 时返回后清空统计
+#[tauri::command]
+async fn metrics<R: Runtime>(
+    export: Option<String>,
+    reset: Option<bool>,
+    app: AppHandle<R>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    let path = match export.map(|name| export_path(&app, &name)).transpose() {
+        Ok(path) => path,
+        Err(err) => return Err(LResponse::default().code(1).data(Value::String(err))),
+    };
+    let metrics = c_manage
+        .client_manage
+        .metrics_collect(reset.unwrap_or(false));
+    if let Some(path) = path {
+        let res = std::fs::create_dir_all(path.parent().unwrap_or(&path))
+            .and_then(|_| std::fs::write(&path, metrics.to_prometheus()));
+        if let Err(err) = res {
+            return Err(LResponse::default()
+                .code(1)
+                .data(Value::String(err.to_string())));
+        }
+    }
+    Ok(LResponse::default().data(json!(metrics.snapshot())))
+}
+
+/// 导出文件只能写在日志目录下
+fn export_path<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || Path::new(name).file_name() != Some(OsStr::new(name)) {
+        return Err(format!("invalid export file name: {}", name));
+    }
+    app.path_resolver()
+        .app_log_dir()
+        .map(|dir| dir.join("metrics").join(name))
+        .ok_or_else(|| "log dir not found".to_string())
+}
+
+/// 设置客户端某个 push 事件发往页面的策略, `policy` 为空时恢复默认.
+/// `event` 为 `*` 时作用于所有没有单独配置的事件. 返回各事件的计数.
+#[tauri::command]
+async fn push_policy<R: Runtime>(
+    id: String,
+    event: String,
+    policy: Option<PushPolicy>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => {
+            client.pushes.set_policy(&event, policy);
+            let stats = serde_json::to_value(client.pushes.stats()).unwrap_or_default();
+            Ok(LResponse::default().data(stats))
+        }
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+/// 客户端各 push 事件收到, 发送和被合并的条数
+#[tauri::command]
+async fn push_stats<R: Runtime>(
+    id: String,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => Ok(LResponse::default().data(json!({
+            "policies": client.pushes.policies(),
+            "stats": client.pushes.stats(),
+        }))),
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+/// 页面处理完需要确认的推送后调用, 返回确认成功的数量
+#[tauri::command]
+async fn push_ack<R: Runtime>(
+    id: String,
+    ids: Vec<String>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => Ok(LResponse::default().data(Value::from(client.ack(&ids)))),
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+/// 重新发送还没有确认的推送, 返回发送的数量
+#[tauri::command]
+async fn push_redeliver<R: Runtime>(
+    id: String,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => Ok(LResponse::default().data(Value::from(client.redeliver()))),
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+/// 注册由服务端推送维护的集合, 注册后按快照和增量推送更新
+#[tauri::command]
+async fn store_register<R: Runtime>(
+    id: String,
+    spec: CollectionSpec,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => {
+            let resync = spec.resync_url.is_some();
+            let name = spec.name.clone();
+            client.store.register(spec);
+            // 注册后立即拉取一次完整列表
+            if resync {
+                if let Ok(StoreAction::Resync(spec)) = client.store.request_resync(&name) {
+                    client.resync(spec);
+                }
+            }
+            Ok(LResponse::default())
+        }
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+#[tauri::command]
+async fn store_query<R: Runtime>(
+    id: String,
+    collection: String,
+    filter: Option<StoreFilter>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => match client.store.query(&collection, &filter.unwrap_or_default()) {
+            Ok(view) => {
+                Ok(LResponse::default().data(serde_json::to_value(view).unwrap_or_default()))
+            }
+            Err(err) => Err(LResponse::default()
+                .code(1)
+                .data(Value::String(err.to_string()))),
+        },
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+/// 订阅过滤后的视图, 集合每次变化都会以 CLIENT_IDENTIFICATION_STORE 事件推送,
+/// 返回订阅 id 和当前视图
+#[tauri::command]
+async fn store_subscribe<R: Runtime>(
+    id: String,
+    collection: String,
+    filter: Option<StoreFilter>,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => match client
+            .store
+            .subscribe(&collection, filter.unwrap_or_default())
+        {
+            Ok(subscription) => Ok(LResponse::default().data(json!(subscription))),
+            Err(err) => Err(LResponse::default()
+                .code(1)
+                .data(Value::String(err.to_string()))),
+        },
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+#[tauri::command]
+async fn store_unsubscribe<R: Runtime>(
+    id: String,
+    subscription: String,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => {
+            Ok(LResponse::default().data(Value::Bool(client.store.unsubscribe(&subscription))))
+        }
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+/// 主动重新拉取集合的完整列表
+#[tauri::command]
+async fn store_resync<R: Runtime>(
+    id: String,
+    collection: String,
+    c_manage: State<'_, ClientState<R>>,
+) -> Result<LResponse, LResponse> {
+    match c_manage.client_manage.get_client(id) {
+        Some(client) => match client.store.request_resync(&collection) {
+            Ok(StoreAction::Resync(spec)) => {
+                client.resync(spec);
+                Ok(LResponse::default())
+            }
+            Ok(_) => Err(LResponse::default()
+                .code(1)
+                .data(Value::String("resync url not set".to_string()))),
+            Err(err) => Err(LResponse::default()
+                .code(1)
+                .data(Value::String(err.to_string()))),
+        },
+        None => Err(LResponse::default()
+            .code(1)
+            .data(Value::String("not found client".to_string()))),
+    }
+}
+
+pub struct ClientState<R: Runtime> {
+    client_manage: Arc<ClientManage>,
+    sink: Arc<WindowSink<R>>,
+}
+
+#[derive(Default)]
+pub struct Builder {
+    inbound: InboundConfig,
+    descriptors: Vec<PathBuf>,
+}
+
 impl Builder {
+    /// 每个客户端入站队列的容量和溢出策略
+    pub fn inbound(mut self, inbound: InboundConfig) -> Self {
+        self.inbound = inbound;
+        self
+    }
+
+    /// 启动时加载的 FileDescriptorSet 文件, 加载失败只记录日志
+    pub fn descriptors(mut self, path: impl Into<PathBuf>) -> Self {
+        self.descriptors.push(path.into());
+        self
+    }
+
     pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
         tauri::plugin::Builder::new("connect")
-            .invoke_handler(tauri::generate_handler![connect, disconnect, send])
+            .invoke_handler(tauri::generate_handler![
+                connect,
+                disconnect,
+                list_clients,
+                attach_window,
+                detach_window,
+                send,
+                start_record,
+                stop_record,
+                replay,
+                inspector_query,
+                inspector_clear,
+                inspector_config,
+                open_inspector,
+                load_descriptors,
+                message_types,
+                metrics,
+                push_policy,
+                push_stats,
+                push_ack,
+                push_redeliver,
+                store_register,
+                store_query,
+                store_subscribe,
+                store_unsubscribe,
+                store_resync
+            ])
             .setup(move |app_handle| {
+                let client_manage = Arc::new(ClientManage::with_inbound(self.inbound));
+                for path in self.descriptors.iter() {
+                    if let Err(err) = client_manage.load_descriptors(path) {
+                        error!("load descriptors {} error: {}", path.display(), err);
+                    }
+                }
+                let sink = Arc::new(WindowSink::new(app_handle.clone()));
+                app_handle.manage(ConnectHandle::new(client_manage.clone(), sink.clone()));
                 app_handle.manage(ClientState {
-                    client_manage: Mutex::new(ClientManage::<R>::new()),
+                    client_manage,
+                    sink,
                 });
                 Ok(())
             })
             .on_event(|app_handle, event| {
+                if let RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } = event
+                {
+                    // 窗口事件在主线程回调, 移除客户端需要在 tokio 运行时中进行
+                    let manage = app_handle.state::<ClientState<R>>();
+                    tauri::async_runtime::block_on(async {
+                        manage.client_manage.remove_window(label);
+                    });
+                }
                 if let RunEvent::Exit = event {
                     let manage = app_handle.state::<ClientState<R>>();
                     let manage = tauri::async_runtime::block_on(async {
-                        manage.client_manage.lock().await.close_all().await
+                        manage.client_manage.close_all().await
                     });
 
                     if let Err(err) = manage {
-                        println!("Failed to close client: {}", err);
+                        error!("failed to close client: {}", err);
                     }
                 }
             })
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use client::{
+    client_manage::{ClientManage, ConnState},
+    clock::ClockSnapshot,
+    emitter::ClientScope,
+    sink::EventSink,
+    typed::{Endpoint, PushEvent, RequestError},
+    w_client::{ClientInfo, ClientOwner, WClient},
+};
+use proto::{
+    message::{Body, Push},
+    MessageBody,
+};
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+/// rust 中创建的客户端所属的窗口, 不存在这个窗口, 事件只会发往 scope 中的其他窗口.
+/// `ConnectHandle::owner` 得到的调用方为 `rust:<名称>`
+pub const RUST_CLIENT_OWNER: &str = "rust";
+
+/// 在 rust 中使用 connect 插件管理的连接, 通过 `app_handle.state::<ConnectHandle>()` 获取.
+/// 页面创建的客户端同样可以通过 id 使用
+#[derive(Clone)]
+pub struct ConnectHandle {
+    manage: Arc<ClientManage>,
+    sink: Arc<dyn EventSink>,
+    owner: String,
+}
+
+impl ConnectHandle {
+    pub(crate) fn new(manage: Arc<ClientManage>, sink: Arc<dyn EventSink>) -> Self {
+        Self {
+            manage,
+            sink,
+            owner: RUST_CLIENT_OWNER.to_string(),
+        }
+    }
+
+    /// 以单独的调用方建立连接, 不同调用方对同一地址的连接是不同的客户端,
+    /// 一方 disconnect 不会影响其他调用方: `handle.owner("report").connect(..)`
+    pub fn owner(&self, name: &str) -> Self {
+        Self {
+            owner: format!("{}:{}", RUST_CLIENT_OWNER, name),
+            ..self.clone()
+        }
+    }
+
+    /// 建立连接并返回客户端 id, 同一调用方的同名连接只会创建一次,
+    /// 再次 connect 返回原来的客户端, 不会影响它正在等待的请求和订阅.
+    /// `scope` 不为 `Window` 时页面也能收到这个客户端的事件
+    pub async fn connect(
+        &self,
+        ip: &str,
+        port: u16,
+        name: Option<String>,
+        scope: ClientScope,
+    ) -> Result<String> {
+        self.manage
+            .add_client(
+                self.sink.clone(),
+                ClientOwner::new(&self.owner),
+                ip.to_string(),
+                port,
+                name,
+                scope,
+            )
+            .await
+    }
+
+    pub fn disconnect(&self, client_id: &str) -> Result<()> {
+        self.manage.remove_client(client_id.to_string())
+    }
+
+    /// 所有客户端, 包括页面创建的
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        self.manage.all_clients()
+    }
+
+    /// 服务端返回非 OK 状态, 超时或发送失败时返回 Err
+    pub async fn request(&self, client_id: &str, url: &str, data: Value) -> Result<Value> {
+        let mut client = self
+            .manage
+            .get_client(client_id.to_string())
+            .ok_or_else(|| anyhow!("not found client"))?;
+        client
+            .request(url.to_string(), Body::from_serialize(data))
+            .await
+            .map_err(|error| anyhow!("request {} error: {}", url, error))
+    }
+
+    /// 按 `Endpoint` 的声明发送请求, 找不到客户端时返回 `RequestError::Local`
+    pub async fn request_typed<E: Endpoint>(
+        &self,
+        client_id: &str,
+        request: &E::Request,
+    ) -> Result<E::Response, RequestError<E::Error>> {
+        let mut client = self
+            .manage
+            .get_client(client_id.to_string())
+            .ok_or_else(|| RequestError::Local("not found client".to_string()))?;
+        client.request_typed::<E>(request).await
+    }
+
+    /// proto service 生成的客户端: `handle.service::<TaskServiceClient<_>>(id)?.list(&query).await`
+    pub fn service<S: From<WClient>>(&self, client_id: &str) -> Result<S> {
+        self.manage
+            .get_client(client_id.to_string())
+            .map(S::from)
+            .ok_or_else(|| anyhow!("not found client"))
+    }
+
+    /// 注册 `P::EVENT` 的推送回调, 返回的 id 用于 `off_push`
+    pub fn on_push<P, F>(&self, client_id: &str, handler: F) -> Result<u64>
+    where
+        P: PushEvent,
+        F: Fn(P::Data) + Send + Sync + 'static,
+    {
+        let client = self
+            .manage
+            .get_client(client_id.to_string())
+            .ok_or_else(|| anyhow!("not found client"))?;
+        Ok(client.handlers.on::<P, F>(handler))
+    }
+
+    pub fn off_push(&self, client_id: &str, id: u64) -> bool {
+        self.manage
+            .get_client(client_id.to_string())
+            .map_or(false, |client| client.handlers.off(id))
+    }
+
+    /// 按顺序去重后的推送, 不受页面设置的推送策略影响.
+    /// 处理得太慢时会丢弃积压的推送, 客户端移除后结束
+    pub fn pushes(&self, client_id: &str) -> Result<impl Stream<Item = Push>> {
+        let client = self
+            .manage
+            .get_client(client_id.to_string())
+            .ok_or_else(|| anyhow!("not found client"))?;
+        Ok(BroadcastStream::new(client.subscribe_pushes()).filter_map(|push| push.ok()))
+    }
+
+    /// 按请求估算的服务端时钟偏差, 服务端时间减去 `offset_ms` 就是本地时间
+    pub fn clock(&self, client_id: &str) -> Result<Option<ClockSnapshot>> {
+        self.manage
+            .get_client(client_id.to_string())
+            .map(|client| client.clock())
+            .ok_or_else(|| anyhow!("not found client"))
+    }
+
+    /// 当前的连接状态, 可以用 `changed()` 等待变化
+    pub fn state(&self, client_id: &str) -> Result<watch::Receiver<ConnState>> {
+        self.manage
+            .conn_state(client_id)
+            .ok_or_else(|| anyhow!("not found client"))
+    }
+}
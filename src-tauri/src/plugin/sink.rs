@@ -0,0 +1,32 @@
+use client::sink::EventSink;
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// 把客户端事件发往 tauri 窗口
+pub struct WindowSink<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> WindowSink<R> {
+    pub fn new(app: AppHandle<R>) -> Self {
+        Self { app }
+    }
+}
+
+impl<R: Runtime> EventSink for WindowSink<R> {
+    fn emit_all(&self, event: &str, payload: &Value) -> Result<(), String> {
+        self.app
+            .emit_all(event, payload)
+            .map_err(|error| error.to_string())
+    }
+
+    fn emit_to(&self, target: &str, event: &str, payload: &Value) -> Result<(), String> {
+        // 已经关闭的窗口跳过, 由窗口销毁事件负责从客户端中移除
+        match self.app.get_window(target) {
+            Some(window) => window
+                .emit(event, payload)
+                .map_err(|error| error.to_string()),
+            None => Ok(()),
+        }
+    }
+}
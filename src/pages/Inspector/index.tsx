@@ -0,0 +1,102 @@
+import { useEffect, useState } from "react";
+import { Button, Form, Input, Space, Table, Tag } from "antd";
+import { appWindow } from "@tauri-apps/api/window";
+import { Event } from "@tauri-apps/api/event";
+import dayjs from "dayjs";
+import {
+    CLIENT_IDENTIFICATION_INSPECTOR,
+    InspectorEntry,
+    formatEventName,
+    invokeCommand,
+} from "../../utils/client/base";
+
+const MAX_ROWS = 1000;
+
+interface InspectorFilter {
+    url?: string;
+    event?: string;
+    sequence?: string;
+}
+
+const Inspector = () => {
+    const [form] = Form.useForm<InspectorFilter>();
+    const [entries, setEntries] = useState<InspectorEntry[]>([]);
+
+    const query = async () => {
+        const filter = form.getFieldsValue();
+        const res = await invokeCommand("inspector_query", {
+            filter: {
+                url: filter.url || undefined,
+                event: filter.event || undefined,
+                sequence: filter.sequence || undefined,
+                limit: MAX_ROWS,
+            },
+        });
+        setEntries(res.data.reverse());
+    };
+
+    const clear = async () => {
+        await invokeCommand("inspector_clear");
+        setEntries([]);
+    };
+
+    useEffect(() => {
+        query();
+        const unListen = appWindow.listen(
+            formatEventName(CLIENT_IDENTIFICATION_INSPECTOR),
+            (message: Event<InspectorEntry>) => {
+                setEntries((entries) => [message.payload, ...entries].slice(0, MAX_ROWS));
+            }
+        );
+        return () => {
+            unListen.then((un) => un());
+        };
+        // eslint-disable-next-line react-hooks/exhaustive-deps
+    }, []);
+
+    return (
+        <div style={{ padding: 12 }}>
+            <Form form={form} layout="inline" onFinish={query}>
+                <Form.Item name="url">
+                    <Input placeholder="url" allowClear />
+                </Form.Item>
+                <Form.Item name="event">
+                    <Input placeholder="event" allowClear />
+                </Form.Item>
+                <Form.Item name="sequence">
+                    <Input placeholder="sequence" allowClear />
+                </Form.Item>
+                <Space>
+                    <Button type="primary" htmlType="submit">查询</Button>
+                    <Button onClick={clear}>清空</Button>
+                </Space>
+            </Form>
+            <Table
+                rowKey="id"
+                size="small"
+                dataSource={entries}
+                pagination={{ pageSize: 50 }}
+                expandable={{
+                    expandedRowRender: (entry) => <pre>{JSON.stringify(entry.message, null, 2)}</pre>,
+                }}
+                columns={[
+                    { title: "时间", dataIndex: "time", render: (time) => dayjs(time).format("HH:mm:ss.SSS") },
+                    {
+                        title: "方向",
+                        dataIndex: "direction",
+                        render: (direction) => <Tag color={direction === "send" ? "blue" : "green"}>{direction}</Tag>,
+                    },
+                    { title: "地址", dataIndex: "address" },
+                    { title: "类型", dataIndex: "kind" },
+                    { title: "url / event", render: (_, entry) => entry.url ?? entry.event },
+                    { title: "sequence", dataIndex: "sequence", ellipsis: true },
+                    { title: "状态", dataIndex: "status" },
+                    { title: "大小", dataIndex: "size" },
+                    { title: "耗时(ms)", dataIndex: "latency" },
+                ]}
+            />
+        </div>
+    );
+};
+
+export default Inspector;
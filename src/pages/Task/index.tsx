@@ -47,6 +47,14 @@ const TaskComp = () => {
         });
     });
 
+    useEffect(() => {
+        // 断线期间漏掉的推送无法补齐时重新拉取列表
+        client.on("resync", getTaskList);
+        return () => {
+            client.off("resync", getTaskList);
+        };
+    }, [queryParam]);
+
     useEffect(() => {
         const handleTaskList = (body: PushData<TaskListValue>) => {
             setData(body.data.list);
@@ -8,6 +8,7 @@ const Home = lazy(() => import("../pages/Home"));
 const NotFound = lazy(() => import("../components/NotFound"));
 const Task = lazy(() => import("../pages/Task"));
 const Connect = lazy(() => import("../pages/Connect"));
+const Inspector = lazy(() => import("../pages/Inspector"));
 
 export interface RouterMapItem extends NonIndexRouteObject {
     tool_id?: string;
@@ -30,6 +31,10 @@ export const routerMap: RouterMapItem[] = [
         path: "/connect",
         element: <Connect />,
     },
+    {
+        path: "/inspector",
+        element: <Inspector />,
+    },
     {
         path: "*",
         element: <NotFound />,
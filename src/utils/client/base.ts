@@ -1,9 +1,36 @@
-export type MessageType = number | string | boolean | object | null | undefined | Array<MessageType>
+import { CommandArgs, CommandResponse, JsonValue, StoreView as StoreViewData } from "./bindings";
 
-export interface LocalResponse<T> {
-    code: number,
-    data: T
-}
+// 事件名和与 rust 共用的类型都在 bindings.ts 中, 由 rust 生成
+export {
+    CLIENT_IDENTIFICATION_RESPONSE,
+    CLIENT_IDENTIFICATION_PUSH,
+    CLIENT_IDENTIFICATION_REQUEST,
+    CLIENT_IDENTIFICATION_CLOSE,
+    CLIENT_IDENTIFICATION_ERROR,
+    CLIENT_IDENTIFICATION_CONNECT_ERROR,
+    CLIENT_IDENTIFICATION_INSPECTOR,
+    CLIENT_IDENTIFICATION_STORE,
+    CLIENT_IDENTIFICATION_RESYNC,
+    InfoType,
+    formatEventName,
+    invokeCommand,
+} from "./bindings";
+export type {
+    ClientInfo,
+    ClientScope,
+    CollectionSpec,
+    InspectorEntry,
+    InspectorFilter,
+    PushPayload,
+    PushPolicy,
+    PushStats,
+    ResyncNotice,
+    StoreFilter,
+} from "./bindings";
+
+export type MessageType = JsonValue | undefined
+
+export type LocalResponse<T> = CommandResponse<T>
 
 export interface Client {
     connect: (options: ClientOptions) => void
@@ -11,21 +38,13 @@ export interface Client {
     send: (url: string, message: MessageType) => void
 }
 
-export interface ClientOptions {
-    ip: string
-    port: number
-}
+/**
+ * connect 命令的参数. 同一个窗口对同一个地址建立多个连接时用 name 区分,
+ * scope 决定事件发往哪些窗口, 默认只发给创建连接的窗口
+ */
+export type ClientOptions = CommandArgs<"connect">
 
-const CLIENT_IDENTIFICATION = "CLIENT_IDENTIFICATION"
-export const CLIENT_IDENTIFICATION_RESPONSE = "CLIENT_IDENTIFICATION_RESPONSE";
-export const CLIENT_IDENTIFICATION_PUSH = "CLIENT_IDENTIFICATION_PUSH";
-export const CLIENT_IDENTIFICATION_REQUEST = "CLIENT_IDENTIFICATION_REQUEST";
-export const CLIENT_IDENTIFICATION_CLOSE = "CLIENT_IDENTIFICATION_CLOSE";
-export const CLIENT_IDENTIFICATION_ERROR = "CLIENT_IDENTIFICATION_ERROR";
-export const CLIENT_IDENTIFICATION_CONNECT_ERROR = "CLIENT_IDENTIFICATION_CONNECT_ERROR"
-
-export const formatEventName = (name: string) => {
-    return `${CLIENT_IDENTIFICATION}::${name}`
+export type StoreView<T> = Omit<StoreViewData, "items"> & {
+    items: T[]
+    subscription?: string
 }
-
-
@@ -0,0 +1,277 @@
+// 由 src-tauri/src/plugin/bindings.rs 生成, 不要手动修改, 运行 `npm run bindings` 更新
+
+import { invoke } from "@tauri-apps/api";
+
+export type JsonValue = number | string | boolean | Array<JsonValue> | { [key in string]?: JsonValue } | null;
+
+export const CLIENT_IDENTIFICATION = "CLIENT_IDENTIFICATION";
+export const CLIENT_IDENTIFICATION_RESPONSE = "CLIENT_IDENTIFICATION_RESPONSE";
+export const CLIENT_IDENTIFICATION_PUSH = "CLIENT_IDENTIFICATION_PUSH";
+export const CLIENT_IDENTIFICATION_REQUEST = "CLIENT_IDENTIFICATION_REQUEST";
+export const CLIENT_IDENTIFICATION_CLOSE = "CLIENT_IDENTIFICATION_CLOSE";
+export const CLIENT_IDENTIFICATION_ERROR = "CLIENT_IDENTIFICATION_ERROR";
+export const CLIENT_IDENTIFICATION_CONNECT_ERROR = "CLIENT_IDENTIFICATION_CONNECT_ERROR";
+export const CLIENT_IDENTIFICATION_INSPECTOR = "CLIENT_IDENTIFICATION_INSPECTOR";
+export const CLIENT_IDENTIFICATION_STORE = "CLIENT_IDENTIFICATION_STORE";
+export const CLIENT_IDENTIFICATION_RESYNC = "CLIENT_IDENTIFICATION_RESYNC";
+
+export const formatEventName = (name: string) => `${CLIENT_IDENTIFICATION}::${name}`;
+
+export type EventPayloads = {
+    [CLIENT_IDENTIFICATION_RESPONSE]: string,
+    [CLIENT_IDENTIFICATION_PUSH]: PushPayload,
+    [CLIENT_IDENTIFICATION_REQUEST]: string,
+    [CLIENT_IDENTIFICATION_CLOSE]: string,
+    [CLIENT_IDENTIFICATION_ERROR]: string,
+    [CLIENT_IDENTIFICATION_CONNECT_ERROR]: string,
+    [CLIENT_IDENTIFICATION_INSPECTOR]: InspectorEntry,
+    [CLIENT_IDENTIFICATION_STORE]: StoreView & { subscription: string },
+    [CLIENT_IDENTIFICATION_RESYNC]: ResyncNotice,
+};
+
+/**
+ * 命令的返回值, 成功和失败都是这个结构, 失败时 code 不为 0
+ */
+export type LResponse = { code: number, data: JsonValue, };
+
+/**
+ * Push 发往页面时的结构
+ */
+export type PushPayload = { event: string, 
+/**
+ * `InfoType` 的值
+ */
+status: number, data: JsonValue, 
+/**
+ * 秒, 与旧版本一致, 带毫秒的小数部分
+ */
+sendTime: number, seq: number, session: string, id: string, ack: boolean, };
+
+export const InfoType = { SUCCESS: 0, ERROR: 1, WARN: 2 } as const;
+export type InfoType = (typeof InfoType)[keyof typeof InfoType];
+
+/**
+ * 客户端事件发往哪些窗口
+ */
+export type ClientScope = { "kind": "window" } | { "kind": "app" } | { "kind": "group", windows: Array<string>, };
+
+export type ClientInfo = { id: string, window: string, address: string, name: string | null, scope: ClientScope, 
+/**
+ * 接收事件的窗口, app 作用域时为空
+ */
+windows: Array<string> | null, 
+/**
+ * 还在等待响应的请求数
+ */
+pendingRequests: number, 
+/**
+ * 还没有被页面确认的推送数
+ */
+pendingAcks: number, };
+
+/**
+ * 单个 push 事件发往页面的策略
+ */
+export type PushPolicy = { "kind": "all" } | { "kind": "latest", intervalMs: number, } | { "kind": "debounce", waitMs: number, maxWaitMs?: number, } | { "kind": "sample", intervalMs: number, };
+
+export type PushStats = { policy: PushPolicy, 
+/**
+ * 服务端推送的条数
+ */
+received: number, 
+/**
+ * 实际发往页面的条数
+ */
+emitted: number, 
+/**
+ * 被后来的消息覆盖而没有发送的条数
+ */
+coalesced: number, };
+
+/**
+ * 一个由服务端维护的集合, 客户端按 `key` 字段以 id 保存副本
+ */
+export type CollectionSpec = { name: string, 
+/**
+ * 元素中作为 id 的字段
+ */
+key?: string, 
+/**
+ * 推送完整列表的事件, 例如 `task-list/update`
+ */
+snapshotEvent?: string, 
+/**
+ * 完整列表在数据中的字段, 为空时数据本身就是列表
+ */
+listField?: string, 
+/**
+ * 推送增量修改的事件
+ */
+patchEvent?: string, 
+/**
+ * 版本不连续时重新拉取完整列表的地址, 例如 `/task/list`
+ */
+resyncUrl?: string, resyncData?: JsonValue, };
+
+/**
+ * 查询条件, 所有条件同时满足
+ */
+export type StoreFilter = { 
+/**
+ * 字段等于给定值, 给定值为数组时表示字段等于其中之一
+ */
+where?: { [key in string]?: JsonValue }, 
+/**
+ * 任意字符串字段包含该文本
+ */
+search?: string, sortBy?: string, desc?: boolean, offset?: number, limit?: number, };
+
+/**
+ * 过滤后的集合视图
+ */
+export type StoreView = { collection: string, version: number, 
+/**
+ * 过滤后, 分页前的总数
+ */
+total: number, items: Array<JsonValue>, };
+
+/**
+ * 新建的订阅和订阅时的视图
+ */
+export type StoreSubscription = { subscription: string, view: StoreView, };
+
+/**
+ * 服务端无法重发缺失的推送, 页面需要重新拉取完整数据
+ */
+export type ResyncNotice = { session: string, 
+/**
+ * 最后一条连续收到的推送
+ */
+after: number, reason: string, };
+
+export type RecordOptions = { 
+/**
+ * 单个文件的最大字节数, 超过后滚动
+ */
+maxBytes?: number, 
+/**
+ * 最多保留的文件数量 (包括正在写入的文件)
+ */
+maxFiles?: number, };
+
+/**
+ * 回放中客户端发出的一个事件
+ */
+export type ReplayEvent = { event: string, payload: JsonValue, };
+
+export type ReplayResult = { 
+/**
+ * 回放的帧数
+ */
+frames: number, 
+/**
+ * 按顺序发出的事件
+ */
+events: Array<ReplayEvent>, };
+
+export type Direction = "send" | "recv";
+
+export type InspectorFilter = { address?: string, kind?: string, direction?: Direction, 
+/**
+ * 包含匹配
+ */
+url?: string, 
+/**
+ * 包含匹配
+ */
+event?: string, sequence?: string, status?: number, since?: number, until?: number, 
+/**
+ * 只返回最新的 n 条
+ */
+limit?: number, };
+
+/**
+ * 检查器中的一条记录
+ */
+export type InspectorEntry = { id: number, 
+/**
+ * 毫秒时间戳
+ */
+time: number, direction: Direction, address: string, kind: string, size: number, url: string | null, event: string | null, sequence: string | null, status: number | null, 
+/**
+ * 响应相对于请求的耗时, 毫秒
+ */
+latency: number | null, message: JsonValue, };
+
+export type LatencySnapshot = { count: number, mean: number, p50: number, p90: number, p99: number, max: number, };
+
+export type UrlSnapshot = { requests: number, ok: number, timeouts: number, sendErrors: number, 
+/**
+ * 非 OK 状态码 -> 次数
+ */
+statuses: { [key in number]?: number }, 
+/**
+ * 毫秒
+ */
+latency: LatencySnapshot, };
+
+export type ClockSnapshot = { 
+/**
+ * 服务端时钟比本地快的毫秒数, 服务端时间减去它就是本地时间
+ */
+offsetMs: number, 
+/**
+ * 估算使用的样本的往返时间, 毫秒
+ */
+rttMs: number, 
+/**
+ * 最近样本往返时间的平均值, 毫秒
+ */
+rttMeanMs: number, samples: number, };
+
+export type ConnectionSnapshot = { bytesIn: number, bytesOut: number, framesIn: number, framesOut: number, invalidFrames: number, pushes: { [key in string]?: number }, urls: { [key in string]?: UrlSnapshot }, 
+/**
+ * 服务端的时钟偏差和往返时间, 服务端没有返回毫秒时间时为空
+ */
+clock: ClockSnapshot | null, };
+
+export type Commands = {
+    connect: { args: { ip: string, port: number, name?: string, scope?: ClientScope, page?: string }, data: string },
+    disconnect: { args: { id: string }, data: null },
+    list_clients: { args: {}, data: Array<ClientInfo> },
+    attach_window: { args: { id: string, label?: string }, data: boolean },
+    detach_window: { args: { id: string, label?: string }, data: null },
+    send: { args: { id: string, data: JsonValue, url: string, proto?: string }, data: JsonValue },
+    start_record: { args: { dir?: string, options?: RecordOptions }, data: string },
+    stop_record: { args: {}, data: null },
+    replay: { args: { id: string, path: string, realtime?: boolean }, data: ReplayResult },
+    inspector_query: { args: { filter?: InspectorFilter }, data: Array<InspectorEntry> },
+    inspector_clear: { args: { address?: string }, data: null },
+    inspector_config: { args: { enabled?: boolean, capacity?: number }, data: null },
+    open_inspector: { args: {}, data: null },
+    load_descriptors: { args: { path: string }, data: Array<string> },
+    message_types: { args: {}, data: Array<string> },
+    metrics: { args: { export?: string, reset?: boolean }, data: { [key in string]?: ConnectionSnapshot } },
+    push_policy: { args: { id: string, event: string, policy?: PushPolicy }, data: { [key in string]?: PushStats } },
+    push_stats: { args: { id: string }, data: { policies: { [key in string]?: PushPolicy }, stats: { [key in string]?: PushStats } } },
+    push_ack: { args: { id: string, ids: Array<string> }, data: number },
+    push_redeliver: { args: { id: string }, data: number },
+    store_register: { args: { id: string, spec: CollectionSpec }, data: null },
+    store_query: { args: { id: string, collection: string, filter?: StoreFilter }, data: StoreView },
+    store_subscribe: { args: { id: string, collection: string, filter?: StoreFilter }, data: StoreSubscription },
+    store_unsubscribe: { args: { id: string, subscription: string }, data: boolean },
+    store_resync: { args: { id: string, collection: string }, data: null },
+};
+
+export type CommandArgs<K extends keyof Commands> = Commands[K]["args"];
+
+export type CommandResponse<T> = Omit<LResponse, "data"> & { data: T };
+
+/**
+ * 调用插件命令, 返回 json 的命令可以用 D 指定 data 的具体类型.
+ * 失败时 reject 的是 data 为错误信息的 `LResponse`
+ */
+export const invokeCommand = <K extends keyof Commands, D = Commands[K]["data"]>(
+    command: K,
+    ...args: {} extends CommandArgs<K> ? [args?: CommandArgs<K>] : [args: CommandArgs<K>]
+): Promise<CommandResponse<D>> => invoke(`plugin:connect|${command}`, args[0]);
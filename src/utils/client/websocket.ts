@@ -8,35 +8,36 @@
  */
 
 import EventEmitter from "events";
-import { dialog, invoke } from "@tauri-apps/api";
+import { dialog } from "@tauri-apps/api";
 import {
     CLIENT_IDENTIFICATION_CLOSE,
     CLIENT_IDENTIFICATION_CONNECT_ERROR,
     CLIENT_IDENTIFICATION_ERROR,
     CLIENT_IDENTIFICATION_PUSH,
     CLIENT_IDENTIFICATION_REQUEST,
+    CLIENT_IDENTIFICATION_RESYNC,
+    CLIENT_IDENTIFICATION_STORE,
     Client,
     ClientOptions,
-    LocalResponse,
     MessageType,
+    PushPayload,
+    PushPolicy,
+    ResyncNotice,
+    CollectionSpec,
+    StoreFilter,
+    StoreView,
     formatEventName,
+    invokeCommand,
 } from "./base";
 import { appWindow } from "@tauri-apps/api/window";
 import { Event } from "@tauri-apps/api/event";
 import { Events } from "..";
 import { Task } from "../../pages/Task/type";
 
-enum PushStatus {
-    SUCCESS = 0,
-    ERROR = 1,
-    WARN = 2,
-}
-
-type EventPayload<T> = {
-    event: string;
-    payload: T;
-    status: PushStatus;
-};
+/**
+ * 页面每次加载生成一次, 同一个客户端再次 connect 时 rust 据此判断页面是否重新加载
+ */
+const PAGE_ID = `${Date.now()}-${Math.random().toString(36).slice(2)}`;
 
 export enum State {
     INIT,
@@ -52,11 +53,8 @@ type SystemEvent = {
     cb: (data: any) => void;
 };
 
-export type PushData<T> = {
+export type PushData<T> = Omit<PushPayload, "data"> & {
     data: T,
-    sendTime: number,
-    event: string,
-    status: PushStatus
 }
 
 export type TaskListValue = {
@@ -69,6 +67,7 @@ type WebsocketEvent = {
     ["connect"]: () => void;
     ["wechat-name/add"]: (data: PushData<string[]>) => void;
     ["task-list/update"]: (body: TaskListValue) => void;
+    ["resync"]: (notice: ResyncNotice) => void;
 };
 
 export class WebsocketClient extends EventEmitter implements Client {
@@ -92,8 +91,20 @@ export class WebsocketClient extends EventEmitter implements Client {
         },
         {
             name: CLIENT_IDENTIFICATION_PUSH,
-            cb: (message: Event<EventPayload<any>>) => {
-                this.emit(message.payload?.event, message.payload);
+            cb: (message: Event<PushPayload>) => {
+                const payload = message.payload;
+                if (!payload?.ack) {
+                    this.emit(payload?.event, payload);
+                    return;
+                }
+                // 需要确认的推送可能被重发, 按 id 去重, 有人处理之后才确认
+                if (this.handledPushes.has(payload.id)) {
+                    return;
+                }
+                if (this.emit(payload.event, payload)) {
+                    this.handledPushes.add(payload.id);
+                    invokeCommand("push_ack", { id: this.client_id!, ids: [payload.id] });
+                }
             },
         },
         {
@@ -120,6 +131,13 @@ export class WebsocketClient extends EventEmitter implements Client {
                 }
             },
         },
+        {
+            // 断线期间漏掉的推送无法补齐, 需要重新拉取数据
+            name: CLIENT_IDENTIFICATION_RESYNC,
+            cb: (message: Event<ResyncNotice>) => {
+                this.emit("resync", message.payload);
+            }
+        },
         {
             name: CLIENT_IDENTIFICATION_CLOSE,
             cb: async () => {
@@ -131,6 +149,8 @@ export class WebsocketClient extends EventEmitter implements Client {
 
     unListen: Promise<() => void>[] = [];
 
+    handledPushes = new Set<string>();
+
     constructor() {
         super();
     }
@@ -141,13 +161,23 @@ export class WebsocketClient extends EventEmitter implements Client {
                 return appWindow.listen(formatEventName(item.name), item.cb);
             })
         );
+        this.redeliver();
+    }
+
+    /**
+     * 页面重新加载后, 让 rust 重新发送还没有确认的推送
+     */
+    async redeliver() {
+        if (this.client_id) {
+            await invokeCommand("push_redeliver", { id: this.client_id });
+        }
     }
 
     stop() {
         this.state = State.CLOSING;
         (async () => {
             if (this.client_id) {
-                await invoke("plugin:connect|disconnect", {
+                await invokeCommand("disconnect", {
                     id: this.client_id,
                 });
             }
@@ -166,8 +196,9 @@ export class WebsocketClient extends EventEmitter implements Client {
         if (this.state === State.CONNECTED) {
             return;
         }
-        const res: LocalResponse<string> = await invoke("plugin:connect|connect", {
+        const res = await invokeCommand("connect", {
             ...this.options,
+            page: PAGE_ID,
         });
 
         if (res.code === 0) {
@@ -176,6 +207,7 @@ export class WebsocketClient extends EventEmitter implements Client {
         }
 
         this.emit("connect");
+        await this.redeliver();
     }
 
     async disconnect() {
@@ -188,10 +220,10 @@ export class WebsocketClient extends EventEmitter implements Client {
         }
         return new Promise<T>(async (resolve, reject) => {
             try {
-                const res: LocalResponse<T> = await invoke("plugin:connect|send", {
-                    id: this.client_id,
+                const res = await invokeCommand<"send", T>("send", {
+                    id: this.client_id!,
                     url: url,
-                    data,
+                    data: data ?? null,
                 });
                 if (res.code === 0) {
                     resolve(res.data);
@@ -202,6 +234,96 @@ export class WebsocketClient extends EventEmitter implements Client {
             }
         });
     }
+
+    /**
+     * 当前窗口的所有连接
+     */
+    static async listClients() {
+        const res = await invokeCommand("list_clients");
+        return res.data;
+    }
+
+    /**
+     * 让另一个窗口 (默认是当前窗口) 也接收这个连接的事件
+     */
+    async attachWindow(label?: string) {
+        const res = await invokeCommand("attach_window", {
+            id: this.client_id!,
+            label,
+        });
+        return res.data;
+    }
+
+    /**
+     * 窗口不再接收这个连接的事件, 没有窗口接收时连接会被关闭
+     */
+    async detachWindow(label?: string) {
+        await invokeCommand("detach_window", { id: this.client_id!, label });
+    }
+
+    async registerCollection(spec: CollectionSpec) {
+        await invokeCommand("store_register", { id: this.client_id!, spec });
+    }
+
+    async queryCollection<T>(collection: string, filter?: StoreFilter) {
+        const res = await invokeCommand<"store_query", StoreView<T>>("store_query", {
+            id: this.client_id!,
+            collection,
+            filter,
+        });
+        return res.data;
+    }
+
+    /**
+     * 订阅集合过滤后的视图, 集合变化时回调, 返回取消订阅的函数
+     */
+    async subscribeCollection<T>(collection: string, filter: StoreFilter, cb: (view: StoreView<T>) => void) {
+        let subscription: string | undefined;
+        let version = -1;
+        // 拿到订阅 id 之前到达的视图先保存, 之后按 version 只回调更新的视图
+        const early: StoreView<T>[] = [];
+        const update = (view: StoreView<T>) => {
+            if (view.version > version) {
+                version = view.version;
+                cb(view);
+            }
+        };
+        const unListen = await appWindow.listen(
+            formatEventName(CLIENT_IDENTIFICATION_STORE),
+            (event: Event<StoreView<T>>) => {
+                if (subscription === undefined) {
+                    early.push(event.payload);
+                } else if (event.payload.subscription === subscription) {
+                    update(event.payload);
+                }
+            }
+        );
+        const res = await invokeCommand<"store_subscribe", { subscription: string; view: StoreView<T> }>("store_subscribe", {
+            id: this.client_id!,
+            collection,
+            filter,
+        });
+        subscription = res.data.subscription;
+        update(res.data.view);
+        early.filter((view) => view.subscription === subscription).forEach(update);
+        return async () => {
+            unListen();
+            await invokeCommand("store_unsubscribe", { id: this.client_id!, subscription: subscription! });
+        };
+    }
+
+    /**
+     * 设置某个 push 事件发往页面的策略, 不传 policy 时恢复为全部发送.
+     * event 为 "*" 时作用于所有没有单独设置的事件
+     */
+    async setPushPolicy(event: string, policy?: PushPolicy) {
+        const res = await invokeCommand("push_policy", {
+            id: this.client_id!,
+            event,
+            policy,
+        });
+        return res.data;
+    }
 }
 
 export const client = new WebsocketClient();